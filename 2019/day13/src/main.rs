@@ -1,13 +1,9 @@
-#[macro_use] extern crate lazy_static;
-
-use failure::Error;
-use itertools::Itertools;
-use regex::Regex;
+use intcode::machine::{Machine, Status};
+use intcode::program::read_program;
+use intcode::trace::{StopReason, Tracer};
 use std::cell::RefCell;
-use std::collections::{BinaryHeap,HashMap,HashSet};
+use std::collections::{BinaryHeap,HashMap};
 use std::cmp::Ordering;
-use std::io::{BufReader,BufRead};
-use std::fs::File;
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
 enum TileType {
@@ -22,144 +18,7 @@ static TILE_TYPES : [TileType; 5] = [
     TileType::EMPTY, TileType::WALL, TileType::BLOCK, TileType::PADDLE, TileType::BALL,
 ];
 
-fn parse_int(val: &str) -> Result<i64, Error> {
-    return Ok(val.parse::<i64>()?);
-}
-
-fn parse_line(line: &str) -> Result<Vec<i64>, Error> {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r",\s*").unwrap();
-    }
-    return RE.split(&line).map(|val| parse_int(&val)).collect();
-}
-
-fn read_input(file: &str) -> Result<Vec<i64>, Error> {
-    let f = File::open(file)?;
-    let br = BufReader::new(f);
-    let parsed : Result<Vec<Vec<i64>>, Error> = br.lines().map(|line| parse_line(&line?)).collect();
-    return Ok(parsed?.into_iter().flatten().collect());
-}
-
-enum ExitType {
-    Finish99,
-    InputAbort,
-}
-
-fn run_program(program_init: Vec<i64>, mut input: impl FnMut() -> Option<i64>, mut output: impl FnMut(i64)) -> ExitType {
-    let relative_base = -99;
-
-    let read_idx = |i: i64, program: &HashMap<i64, i64>| -> i64 {
-        if i < 0 {
-            panic!("Index {} outside of program bound ({})", i, program.len());
-        }
-        match program.get(&i) {
-            Some(&v) => { return v; },
-            None => { return 0; },
-        };
-    };
-
-    let write_idx = |i: i64, value: i64, program: &mut HashMap<i64, i64>| {
-        if i < 0 {
-            panic!("Index {} outside of program bound ({})", i, program.len());
-        }
-        program.insert(i, value);
-    };
-
-    let resolve_idx = |pc: i64, num: i64, modes: i64, program: &HashMap<i64, i64>| -> i64 {
-        let i = pc + num;
-        let mut flag;
-        if num > 0 {
-            flag = modes;
-            for _ in 0..num-1 {
-                flag /= 10;
-            }
-            flag %= 10;
-        } else {
-            flag = 1;
-        }
-        if flag == 0 {
-            return read_idx(i, program);
-        } else if flag == 1 {
-            return i;
-        } else if flag == 2 {
-            return read_idx(i, program) + program[&relative_base];
-        } else {
-            panic!("Bad flag at {}: {}", i, flag);
-        }
-    };
-
-    let get = |pc: i64, num: i64, modes: i64, program: &HashMap<i64, i64>| -> i64 {
-        return read_idx(resolve_idx(pc, num, modes, program), program);
-    };
-
-    let set = |pc: i64, num: i64, modes: i64, value: i64, program: &mut HashMap<i64, i64>| {
-        return write_idx(resolve_idx(pc, num, modes, program), value, program);
-    };
-
-    let mut program : HashMap<i64, i64> = HashMap::new();
-    for i in 0..program_init.len() {
-        program.insert(i as i64, program_init[i]);
-    }
-    program.insert(relative_base, 0);
-
-    let mut pc : i64 = 0;
-    loop {
-        if pc >= program.len() as i64 || pc < 0 {
-            panic!("PC {} outside of program bound ({})", pc, program.len());
-        }
-        let opcode = get(pc, 0, 0, &program) % 100;
-        let modes = get(pc, 0, 0, &program) / 100;
-        // println!("Executing at pc={} opcode={}, modes={}", pc, opcode, modes);
-        if opcode == 1 {
-            let value = get(pc, 1, modes, &program) + get(pc, 2, modes, &program);
-            set(pc, 3, modes, value, &mut program);
-            pc += 4;
-        } else if opcode == 2 {
-            let value = get(pc, 1, modes, &program) * get(pc, 2, modes, &program);
-            set(pc, 3, modes, value, &mut program);
-            pc += 4;
-        } else if opcode == 3 {
-            let value = input();
-            match value {
-                Some(v) => {
-                    set(pc, 1, modes, v, &mut program);
-                    pc += 2;
-                },
-                None => {
-                    return ExitType::InputAbort;
-                }
-            }
-        } else if opcode == 4 {
-            output(get(pc, 1, modes, &program));
-            pc += 2;
-        } else if opcode == 5 || opcode == 6 {
-            if (get(pc, 1, modes, &program) == 0) == (opcode == 6) {
-                pc = get(pc, 2, modes, &program);
-            } else {
-                pc += 3;
-            }
-        } else if opcode == 7 {
-            let value = if get(pc, 1, modes, &program) < get(pc, 2, modes, &program) { 1 } else { 0 };
-            set(pc, 3, modes, value, &mut program);
-            pc += 4;
-        } else if opcode == 8 {
-            let value = if get(pc, 1, modes, &program) == get(pc, 2, modes, &program) { 1 } else { 0 };
-            set(pc, 3, modes, value, &mut program);
-            pc += 4;
-        } else if opcode == 9 {
-            let value = get(pc, 1, modes, &program);
-            *program.get_mut(&relative_base).unwrap() += value;
-            pc += 2;
-        } else if opcode == 99 {
-            // println!("Saw 99, exiting");
-            return ExitType::Finish99;
-        } else {
-            panic!("Unknown opcode at {}: {}", pc, get(pc, 0, 0, &program));
-        }
-    }
-}
-
-fn play_game(program: Vec<i64>, mut input: impl FnMut(&HashMap<(i64, i64), TileType>) -> Option<i64>) ->
+fn play_game(program: Vec<i64>, tracer: Option<&mut Tracer>, mut input: impl FnMut(&HashMap<(i64, i64), TileType>) -> Option<i64>) ->
         (HashMap<(i64, i64), TileType>, Option<i64>) {
     let mut stage = 0;
     let mut cur_x = 0;
@@ -193,9 +52,15 @@ fn play_game(program: Vec<i64>, mut input: impl FnMut(&HashMap<(i64, i64), TileT
         }
     };
 
-    match run_program(program, input_fn, output_fn) {
-        ExitType::InputAbort => { return (grid_ref.into_inner(), None); },
-        ExitType::Finish99 => { return (grid_ref.into_inner(), Some(score)); },
+    let mut machine = Machine::new(&program);
+    match tracer {
+        Some(tracer) => { check_stop(tracer.run_with_io(&mut machine, input_fn, output_fn)); },
+        None => { machine.run(input_fn, output_fn); },
+    }
+    match machine.status() {
+        Status::WaitingForInput => { return (grid_ref.into_inner(), None); },
+        Status::Halted => { return (grid_ref.into_inner(), Some(score)); },
+        Status::Running => { panic!("Machine stopped while still running?"); },
     }
 }
 
@@ -270,28 +135,65 @@ impl PartialOrd for State {
     }
 }
 
-fn solve_game_tree(program: Vec<i64>) -> (Vec<i64>, i64) {
+fn update_tiles(outputs: Vec<i64>, tiles: &mut HashMap<(i64, i64), TileType>, score: &mut i64) {
+    for out in outputs.chunks(3) {
+        if out[0] == -1 && out[1] == 0 {
+            *score = out[2];
+        } else {
+            tiles.insert((out[0], out[1]), TILE_TYPES[out[2] as usize]);
+        }
+    }
+}
+
+// Nothing sets breakpoints, watchpoints or a step limit, so the tracer should
+// only ever stop where the machine would have
+fn check_stop(reason: StopReason) -> Status {
+    match reason {
+        StopReason::Halted => { return Status::Halted; },
+        StopReason::WaitingForInput => { return Status::WaitingForInput; },
+        _ => { panic!("Tracer stopped unexpectedly: {:?}", reason); },
+    }
+}
+
+// Runs until the machine halts or needs input, through the tracer if there is
+// one
+fn run_until_input(machine: &mut Machine, tracer: Option<&mut Tracer>) -> Status {
+    match tracer {
+        Some(tracer) => { return check_stop(tracer.run(machine)); },
+        None => { return machine.run_until_input(); },
+    }
+}
+
+fn solve_game_tree(program: Vec<i64>, mut tracer: Option<&mut Tracer>) -> (Vec<i64>, i64) {
     let moves = vec![-1, 0, 1];
 
+    // Keep the machine as of the end of each sequence in the heap, so we can
+    // pick up from there instead of replaying the whole sequence
+    let mut saved : HashMap<Vec<i64>, (Machine, HashMap<(i64, i64), TileType>, i64)> = HashMap::new();
+    saved.insert(vec![], (Machine::new(&program), HashMap::new(), 0));
+
     let mut heap = BinaryHeap::new();
     heap.push(State { blocks: std::usize::MAX, seq: vec![] });
-    while let Some(State { blocks, seq }) = heap.pop() {
-        let mut cpseq = seq.to_vec();
-        let mut input = |_g: &HashMap<(i64, i64), TileType>| { return Some(cpseq.remove(0)); };
-        let (tiles, score) = play_game(program.to_vec(), input);
+    while let Some(State { blocks: _, seq }) = heap.pop() {
+        let (mut machine, mut tiles, mut score) = saved.remove(&seq).unwrap();
+        let status = run_until_input(&mut machine, tracer.as_deref_mut());
+        update_tiles(machine.take_outputs(), &mut tiles, &mut score);
         let block_count = tiles.values().filter(|v| *v == &TileType::BLOCK).count();
-        match score {
-            Some(s) => {
-                println!("Exit after {:?}, score is {}, blocks is {}", seq, s, block_count);
+        match status {
+            Status::Halted => {
+                println!("Exit after {:?}, score is {}, blocks is {}", seq, score, block_count);
                 if block_count == 0 {
-                    return (seq, s);
+                    return (seq, score);
                 }
             },
-            None => {
+            _ => {
                 println!("Ran out after {:?}, blocks is {}", seq, block_count);
                 for m in &moves {
                     let mut next = seq.to_vec();
                     next.push(*m);
+                    let mut next_machine = machine.fork();
+                    next_machine.push_input(*m);
+                    saved.insert(next.clone(), (next_machine, tiles.clone(), score));
                     heap.push(State { blocks: block_count, seq: next });
                 }
             }
@@ -309,7 +211,7 @@ fn prompt(msg: &str) -> i64 {
     return value;
 }
 
-fn solve_game_manual(program: Vec<i64>) -> (Vec<i64>, i64) {
+fn solve_game_manual(program: Vec<i64>, tracer: Option<&mut Tracer>) -> (Vec<i64>, i64) {
     let moves = vec![-1, 0, 1];
     let mut seq = Vec::new();
     let input = |grid: &HashMap<(i64, i64), TileType>| {
@@ -319,7 +221,7 @@ fn solve_game_manual(program: Vec<i64>) -> (Vec<i64>, i64) {
         return Some(mmove);
     };
 
-    let (tiles, score) = play_game(program.to_vec(), input);
+    let (tiles, score) = play_game(program.to_vec(), tracer, input);
     let block_count = tiles.values().filter(|v| *v == &TileType::BLOCK).count();
     match score {
         Some(s) => {
@@ -333,7 +235,7 @@ fn solve_game_manual(program: Vec<i64>) -> (Vec<i64>, i64) {
     }
 }
 
-fn solve_game_under(program: Vec<i64>) -> (Vec<i64>, i64) {
+fn solve_game_under(program: Vec<i64>, tracer: Option<&mut Tracer>) -> (Vec<i64>, i64) {
     let moves = vec![-1, 0, 1];
     let mut seq = Vec::new();
     let input = |grid: &HashMap<(i64, i64), TileType>| {
@@ -354,7 +256,7 @@ fn solve_game_under(program: Vec<i64>) -> (Vec<i64>, i64) {
         return Some(mmove);
     };
 
-    let (tiles, score) = play_game(program.to_vec(), input);
+    let (tiles, score) = play_game(program.to_vec(), tracer, input);
     let block_count = tiles.values().filter(|v| *v == &TileType::BLOCK).count();
     match score {
         Some(s) => {
@@ -369,19 +271,50 @@ fn solve_game_under(program: Vec<i64>) -> (Vec<i64>, i64) {
 }
 
 fn main() {
-    let args : Vec<String> = std::env::args().collect();
+    let mut args : Vec<String> = std::env::args().collect();
+    // --trace prints every instruction the machine runs
+    let mut tracer = match args.iter().position(|a| a == "--trace") {
+        Some(idx) => {
+            args.remove(idx);
+            let mut tracer = Tracer::new();
+            tracer.verbose = true;
+            Some(tracer)
+        },
+        None => None,
+    };
     if args[1] == "1" {
         println!("Doing part 1");
-        let program = read_input(&args[2]).unwrap();
-        let (tiles, _) = play_game(program, |_g| { None });
+        let program = read_program(&args[2]).unwrap();
+        let (tiles, _) = play_game(program, tracer.as_mut(), |_g| { None });
         let block_count = tiles.values().filter(|v| *v == &TileType::BLOCK).count();
         print_grid(&tiles);
         println!("Found {} blocks", block_count);
     } else {
         println!("Doing part 2");
-        let mut program = read_input(&args[2]).unwrap();
+        let mut program = read_program(&args[2]).unwrap();
         program[0] = 2;
-        let (seq, score) = solve_game_under(program);
+        let (seq, score) = solve_game_under(program, tracer.as_mut());
         println!("Final score: {} after {} moves", score, seq.len());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_game_tree() {
+        // draws one block, reads a move, and only clears the block (and scores 5)
+        // if the move was 1, so the search has to fork past the first two
+        let program = vec![
+            104, 1, 104, 0, 104, 2,
+            3, 100,
+            1008, 100, 1, 101,
+            1006, 101, 27,
+            104, 1, 104, 0, 104, 0,
+            104, -1, 104, 0, 104, 5,
+            99,
+        ];
+        assert_eq!(solve_game_tree(program, None), (vec![1], 5));
+    }
+}
@@ -1,13 +1,7 @@
-#[macro_use] extern crate lazy_static;
-
-use failure::Error;
-use itertools::Itertools;
-use regex::Regex;
-use std::cell::RefCell;
-use std::collections::{BinaryHeap,HashMap,HashSet};
-use std::cmp::Ordering;
-use std::io::{BufReader,BufRead};
-use std::fs::File;
+use intcode::machine::Machine;
+use intcode::program::read_program;
+use intcode::trace::{StopReason, Tracer};
+use std::collections::HashMap;
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
 enum TileType {
@@ -36,193 +30,28 @@ static DIRECTIONS : [Direction; 4] = [
     NORTH, SOUTH, WEST, EAST
 ];
 
-fn parse_int(val: &str) -> Result<i64, Error> {
-    return Ok(val.parse::<i64>()?);
-}
-
-fn parse_line(line: &str) -> Result<Vec<i64>, Error> {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r",\s*").unwrap();
-    }
-    return RE.split(&line).map(|val| parse_int(&val)).collect();
-}
-
-fn read_input(file: &str) -> Result<Vec<i64>, Error> {
-    let f = File::open(file)?;
-    let br = BufReader::new(f);
-    let parsed : Result<Vec<Vec<i64>>, Error> = br.lines().map(|line| parse_line(&line?)).collect();
-    return Ok(parsed?.into_iter().flatten().collect());
-}
-
-enum ExitType {
-    Finish99,
-    InputAbort,
-    OutputAbort,
-}
-
-fn run_program(
-    program_init: &Vec<i64>,
-    mut input: impl FnMut() -> Option<i64>,
-    mut output: impl FnMut(i64, &HashMap<i64, i64>) -> bool,
-) -> ExitType {
-    let mut program : HashMap<i64, i64> = HashMap::new();
-    for i in 0..program_init.len() {
-        program.insert(i as i64, program_init[i]);
-    }
-    return run_program_state(program, input, output);
-}
-
-fn run_program_state(
-    mut program: HashMap<i64, i64>,
-    mut input: impl FnMut() -> Option<i64>,
-    mut output: impl FnMut(i64, &HashMap<i64, i64>) -> bool,
-) -> ExitType {
-    let pc_key = -98;
-    let relative_base = -99;
-
-    if !program.contains_key(&pc_key) {
-        program.insert(pc_key, 0);
-    }
-    if !program.contains_key(&relative_base) {
-        program.insert(relative_base, 0);
-    }
-
-    let read_idx = |i: i64, program: &HashMap<i64, i64>| -> i64 {
-        if i < 0 {
-            panic!("Index {} outside of program bound ({})", i, program.len());
-        }
-        match program.get(&i) {
-            Some(&v) => { return v; },
-            None => { return 0; },
-        };
-    };
-
-    let write_idx = |i: i64, value: i64, program: &mut HashMap<i64, i64>| {
-        if i < 0 {
-            panic!("Index {} outside of program bound ({})", i, program.len());
-        }
-        program.insert(i, value);
-    };
-
-    let resolve_idx = |pc: i64, num: i64, modes: i64, program: &HashMap<i64, i64>| -> i64 {
-        let i = pc + num;
-        let mut flag;
-        if num > 0 {
-            flag = modes;
-            for _ in 0..num-1 {
-                flag /= 10;
-            }
-            flag %= 10;
-        } else {
-            flag = 1;
-        }
-        if flag == 0 {
-            return read_idx(i, program);
-        } else if flag == 1 {
-            return i;
-        } else if flag == 2 {
-            return read_idx(i, program) + program[&relative_base];
-        } else {
-            panic!("Bad flag at {}: {}", i, flag);
-        }
-    };
-
-    let get = |pc: i64, num: i64, modes: i64, program: &HashMap<i64, i64>| -> i64 {
-        return read_idx(resolve_idx(pc, num, modes, program), program);
-    };
-
-    let set = |pc: i64, num: i64, modes: i64, value: i64, program: &mut HashMap<i64, i64>| {
-        return write_idx(resolve_idx(pc, num, modes, program), value, program);
-    };
-
-    loop {
-        let pc = program[&pc_key];
-        if pc >= program.len() as i64 || pc < 0 {
-            panic!("PC {} outside of program bound ({})", pc, program.len());
-        }
-        let opcode = get(pc, 0, 0, &program) % 100;
-        let modes = get(pc, 0, 0, &program) / 100;
-        // println!("Executing at pc={} opcode={}, modes={}", pc, opcode, modes);
-        if opcode == 1 {
-            let value = get(pc, 1, modes, &program) + get(pc, 2, modes, &program);
-            set(pc, 3, modes, value, &mut program);
-            *program.get_mut(&pc_key).unwrap() += 4;
-        } else if opcode == 2 {
-            let value = get(pc, 1, modes, &program) * get(pc, 2, modes, &program);
-            set(pc, 3, modes, value, &mut program);
-            *program.get_mut(&pc_key).unwrap() += 4;
-        } else if opcode == 3 {
-            let value = input();
-            match value {
-                Some(v) => {
-                    set(pc, 1, modes, v, &mut program);
-                    *program.get_mut(&pc_key).unwrap() += 2;
-                },
-                None => {
-                    return ExitType::InputAbort;
-                }
-            }
-        } else if opcode == 4 {
-            let val = get(pc, 1, modes, &program);
-            *program.get_mut(&pc_key).unwrap() += 2;
-            if output(val, &program) {
-                return ExitType::OutputAbort;
-            }
-        } else if opcode == 5 || opcode == 6 {
-            if (get(pc, 1, modes, &program) == 0) == (opcode == 6) {
-                *program.get_mut(&pc_key).unwrap() = get(pc, 2, modes, &program);
-            } else {
-                *program.get_mut(&pc_key).unwrap() += 3;
-            }
-        } else if opcode == 7 {
-            let value = if get(pc, 1, modes, &program) < get(pc, 2, modes, &program) { 1 } else { 0 };
-            set(pc, 3, modes, value, &mut program);
-            *program.get_mut(&pc_key).unwrap() += 4;
-        } else if opcode == 8 {
-            let value = if get(pc, 1, modes, &program) == get(pc, 2, modes, &program) { 1 } else { 0 };
-            set(pc, 3, modes, value, &mut program);
-            *program.get_mut(&pc_key).unwrap() += 4;
-        } else if opcode == 9 {
-            let value = get(pc, 1, modes, &program);
-            *program.get_mut(&relative_base).unwrap() += value;
-            *program.get_mut(&pc_key).unwrap() += 2;
-        } else if opcode == 99 {
-            // println!("Saw 99, exiting");
-            return ExitType::Finish99;
-        } else {
-            panic!("Unknown opcode at {}: {}", pc, get(pc, 0, 0, &program));
-        }
-    }
-}
-
 fn explore_maze2(program: Vec<i64>) -> HashMap<(i64, i64), TileType> {
-    let mut dirs = vec![1, 1, 3, 3, 3];
-    let input_fn = || -> Option<i64> {
-        return Some(dirs.remove(0));
-    };
-
-    let output_fn = |i: i64, program: &HashMap<i64, i64>| -> bool {
+    let mut machine = Machine::new(&program);
+    machine.push_inputs(vec![1, 1, 3, 3, 3]);
+    while let Some(i) = machine.run_until_output() {
         println!("Output: {}", i);
-        println!("HM: {:?}", program);
-        return false;
-    };
-    run_program(&program, input_fn, output_fn);
+        println!("Memory: {:?}", machine.memory());
+    }
     return HashMap::new();
 }
 
 struct State {
     distance: i32,
-    program: HashMap<i64, i64>,
+    machine: Machine,
 }
 
-fn explore_maze(program: Vec<i64>) -> (HashMap<(i64, i64), TileType>, i32) {
+fn explore_maze(program: Vec<i64>, mut tracer: Option<&mut Tracer>) -> (HashMap<(i64, i64), TileType>, i32) {
     let mut grid : HashMap<(i64, i64), TileType> = HashMap::new();
     let mut program_states : HashMap<(i64, i64), State> = HashMap::new();
     let mut positions = Vec::new();
 
     grid.insert((0, 0), TileType::EMPTY);
-    let hash_p = program.iter().enumerate().map(|(i, item)| (i as i64, *item)).collect();
-    program_states.insert((0, 0), State { distance: 0, program: hash_p });
+    program_states.insert((0, 0), State { distance: 0, machine: Machine::new(&program) });
     positions.push((0, 0));
 
     let mut oxy_distance = -1;
@@ -235,56 +64,54 @@ fn explore_maze(program: Vec<i64>) -> (HashMap<(i64, i64), TileType>, i32) {
             let next_p = (pos.0 + dir.x, pos.1 + dir.y);
             if grid.get(&next_p).is_none() {
                 // println!("No data yet for {:?}", dir);
-                match explore_dir(&state.program, dir.clone()) {
+                match explore_dir(&state.machine, dir.clone(), tracer.as_deref_mut()) {
                     (TileType::WALL, _) => {
                         grid.insert(next_p, TileType::WALL);
                         // println!("Is a wall: {:?}", next_p);
                     },
-                    (TileType::EMPTY, new_program) => {
+                    (TileType::EMPTY, new_machine) => {
                         grid.insert(next_p, TileType::EMPTY);
                         positions.push(next_p);
-                        new_states.push((next_p, State { distance: state.distance + 1, program: new_program }));
+                        new_states.push((next_p, State { distance: state.distance + 1, machine: new_machine }));
                         // println!("Is empty: {:?}", next_p);
                     },
-                    (TileType::OXYGEN, new_program) => {
+                    (TileType::OXYGEN, new_machine) => {
                         grid.insert(next_p, TileType::OXYGEN);
                         positions.push(next_p);
-                        new_states.push((next_p, State { distance: state.distance + 1, program: new_program }));
+                        new_states.push((next_p, State { distance: state.distance + 1, machine: new_machine }));
                         oxy_distance = state.distance + 1;
                         // println!("Is oxy: {:?}", next_p);
                     },
                 }
             }
         }
-        for (next_p, new_program) in new_states {
-            program_states.insert(next_p, new_program);
+        for (next_p, new_state) in new_states {
+            program_states.insert(next_p, new_state);
         }
     }
 
     return (grid, oxy_distance);
 }
 
-fn explore_dir(program: &HashMap<i64, i64>, dir: Direction) -> (TileType, HashMap<i64, i64>) {
-    let input_fn = || -> Option<i64> {
-        return Some(dir.value);
-    };
-
-    let mut ret = (TileType::WALL, HashMap::new());
-    let output_fn = |i: i64, program: &HashMap<i64, i64>| -> bool {
-        if i == 0 {
-            return true;
-        } else if i == 1 || i == 2 {
-            ret = (if i == 2 { TileType::OXYGEN } else { TileType::EMPTY }, program.clone());
-            return true;
-        } else {
-            panic!("Unexpected output: {}", i);
-        }
+fn explore_dir(machine: &Machine, dir: Direction, tracer: Option<&mut Tracer>) -> (TileType, Machine) {
+    let mut machine = machine.fork();
+    machine.push_input(dir.value);
+    let output = match tracer {
+        Some(tracer) => match tracer.run_until_output(&mut machine) {
+            Ok(i) => Some(i),
+            // nothing sets breakpoints, watchpoints or a step limit, so these
+            // are the only places the tracer stops without output
+            Err(StopReason::Halted) | Err(StopReason::WaitingForInput) => None,
+            Err(reason) => { panic!("Tracer stopped unexpectedly: {:?}", reason); },
+        },
+        None => machine.run_until_output(),
     };
-
-    match run_program_state(program.clone(), input_fn, output_fn) {
-        ExitType::OutputAbort => { return ret; },
-        ExitType::InputAbort => { panic!("Unexpected input abort"); },
-        ExitType::Finish99 => { panic!("Unexpected finish"); },
+    match output {
+        Some(0) => { return (TileType::WALL, machine); },
+        Some(1) => { return (TileType::EMPTY, machine); },
+        Some(2) => { return (TileType::OXYGEN, machine); },
+        Some(i) => { panic!("Unexpected output: {}", i); },
+        None => { panic!("Unexpected stop: {:?}", machine.status()); },
     }
 }
 
@@ -360,17 +187,27 @@ fn floodfill_grid(grid: &HashMap<(i64, i64), TileType>, init_pos: (i64, i64)) ->
 }
 
 fn main() {
-    let args : Vec<String> = std::env::args().collect();
+    let mut args : Vec<String> = std::env::args().collect();
+    // --trace prints every instruction the machine runs
+    let mut tracer = match args.iter().position(|a| a == "--trace") {
+        Some(idx) => {
+            args.remove(idx);
+            let mut tracer = Tracer::new();
+            tracer.verbose = true;
+            Some(tracer)
+        },
+        None => None,
+    };
     if args[1] == "1" {
         println!("Doing part 1");
-        let program = read_input(&args[2]).unwrap();
-        let (maze, dist) = explore_maze(program);
+        let program = read_program(&args[2]).unwrap();
+        let (maze, dist) = explore_maze(program, tracer.as_mut());
         print_grid(&maze);
         println!("Distance: {}", dist);
     } else {
         println!("Doing part 2");
-        let program = read_input(&args[2]).unwrap();
-        let (maze, dist) = explore_maze(program);
+        let program = read_program(&args[2]).unwrap();
+        let (maze, dist) = explore_maze(program, tracer.as_mut());
         let oxy_loc = maze.iter().filter(|e| *e.1 == TileType::OXYGEN).map(|(k, v)| k).next().unwrap();
         let ff_time = floodfill_grid(&maze, *oxy_loc);
         println!("Total floodfill time: {}", ff_time);
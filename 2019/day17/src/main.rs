@@ -1,150 +1,7 @@
-#[macro_use] extern crate lazy_static;
-
-use failure::Error;
+use intcode::ascii::{AsciiMachine, Outcome};
+use intcode::program::read_program;
 use itertools::Itertools;
-use regex::Regex;
-use std::cell::RefCell;
-use std::collections::{BinaryHeap,HashMap,HashSet};
-use std::cmp::Ordering;
-use std::io::{BufReader,BufRead};
-use std::fs::File;
-
-fn parse_int(val: &str) -> Result<i64, Error> {
-    return Ok(val.parse::<i64>()?);
-}
-
-fn parse_line(line: &str) -> Result<Vec<i64>, Error> {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r",\s*").unwrap();
-    }
-    return RE.split(&line).map(|val| parse_int(&val)).collect();
-}
-
-fn read_input(file: &str) -> Result<Vec<i64>, Error> {
-    let f = File::open(file)?;
-    let br = BufReader::new(f);
-    let parsed : Result<Vec<Vec<i64>>, Error> = br.lines().map(|line| parse_line(&line?)).collect();
-    return Ok(parsed?.into_iter().flatten().collect());
-}
-
-enum ExitType {
-    Finish99,
-    InputAbort,
-}
-
-fn run_program(program_init: &Vec<i64>, mut input: impl FnMut() -> Option<i64>, mut output: impl FnMut(i64)) -> ExitType {
-    let relative_base = -99;
-
-    let read_idx = |i: i64, program: &HashMap<i64, i64>| -> i64 {
-        if i < 0 {
-            panic!("Index {} outside of program bound ({})", i, program.len());
-        }
-        match program.get(&i) {
-            Some(&v) => { return v; },
-            None => { return 0; },
-        };
-    };
-
-    let write_idx = |i: i64, value: i64, program: &mut HashMap<i64, i64>| {
-        if i < 0 {
-            panic!("Index {} outside of program bound ({})", i, program.len());
-        }
-        program.insert(i, value);
-    };
-
-    let resolve_idx = |pc: i64, num: i64, modes: i64, program: &HashMap<i64, i64>| -> i64 {
-        let i = pc + num;
-        let mut flag;
-        if num > 0 {
-            flag = modes;
-            for _ in 0..num-1 {
-                flag /= 10;
-            }
-            flag %= 10;
-        } else {
-            flag = 1;
-        }
-        if flag == 0 {
-            return read_idx(i, program);
-        } else if flag == 1 {
-            return i;
-        } else if flag == 2 {
-            return read_idx(i, program) + program[&relative_base];
-        } else {
-            panic!("Bad flag at {}: {}", i, flag);
-        }
-    };
-
-    let get = |pc: i64, num: i64, modes: i64, program: &HashMap<i64, i64>| -> i64 {
-        return read_idx(resolve_idx(pc, num, modes, program), program);
-    };
-
-    let set = |pc: i64, num: i64, modes: i64, value: i64, program: &mut HashMap<i64, i64>| {
-        return write_idx(resolve_idx(pc, num, modes, program), value, program);
-    };
-
-    let mut program : HashMap<i64, i64> = HashMap::new();
-    for i in 0..program_init.len() {
-        program.insert(i as i64, program_init[i]);
-    }
-    program.insert(relative_base, 0);
-
-    let mut pc : i64 = 0;
-    loop {
-        if pc >= program.len() as i64 || pc < 0 {
-            panic!("PC {} outside of program bound ({})", pc, program.len());
-        }
-        let opcode = get(pc, 0, 0, &program) % 100;
-        let modes = get(pc, 0, 0, &program) / 100;
-        // println!("Executing at pc={} opcode={}, modes={}", pc, opcode, modes);
-        if opcode == 1 {
-            let value = get(pc, 1, modes, &program) + get(pc, 2, modes, &program);
-            set(pc, 3, modes, value, &mut program);
-            pc += 4;
-        } else if opcode == 2 {
-            let value = get(pc, 1, modes, &program) * get(pc, 2, modes, &program);
-            set(pc, 3, modes, value, &mut program);
-            pc += 4;
-        } else if opcode == 3 {
-            let value = input();
-            match value {
-                Some(v) => {
-                    set(pc, 1, modes, v, &mut program);
-                    pc += 2;
-                },
-                None => {
-                    return ExitType::InputAbort;
-                }
-            }
-        } else if opcode == 4 {
-            output(get(pc, 1, modes, &program));
-            pc += 2;
-        } else if opcode == 5 || opcode == 6 {
-            if (get(pc, 1, modes, &program) == 0) == (opcode == 6) {
-                pc = get(pc, 2, modes, &program);
-            } else {
-                pc += 3;
-            }
-        } else if opcode == 7 {
-            let value = if get(pc, 1, modes, &program) < get(pc, 2, modes, &program) { 1 } else { 0 };
-            set(pc, 3, modes, value, &mut program);
-            pc += 4;
-        } else if opcode == 8 {
-            let value = if get(pc, 1, modes, &program) == get(pc, 2, modes, &program) { 1 } else { 0 };
-            set(pc, 3, modes, value, &mut program);
-            pc += 4;
-        } else if opcode == 9 {
-            let value = get(pc, 1, modes, &program);
-            *program.get_mut(&relative_base).unwrap() += value;
-            pc += 2;
-        } else if opcode == 99 {
-            // println!("Saw 99, exiting");
-            return ExitType::Finish99;
-        } else {
-            panic!("Unknown opcode at {}: {}", pc, get(pc, 0, 0, &program));
-        }
-    }
-}
+use std::collections::HashMap;
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
 enum TileType {
@@ -176,25 +33,18 @@ struct Robot {
 }
 
 fn view_cameras(program: &Vec<i64>) -> (Robot, HashMap<(i64, i64), TileType>) {
-    let robot_ref : RefCell<Robot> = RefCell::new(Robot { x: 0, y: 0, dir: None });
-    let grid_ref : RefCell<HashMap<(i64, i64), TileType>> = RefCell::new(HashMap::new());
+    let mut robot = Robot { x: 0, y: 0, dir: None };
+    let mut grid = HashMap::new();
 
-    let input_fn = || -> Option<i64> {
+    let mut machine = AsciiMachine::new(program);
+    if machine.run() != Outcome::Halted {
         panic!("Didn't expect input!");
-    };
+    }
 
     let dir_map : HashMap<char, Direction> = DIRECTIONS.iter().map(|d| (d.c, *d)).collect();
-    let mut grid_x = 0;
-    let mut grid_y = 0;
-    let output_fn = |i: i64| {
-        let mut robot = robot_ref.borrow_mut();
-        let mut grid = grid_ref.borrow_mut();
-
-        let c = std::char::from_u32(i as u32).unwrap();
-        if c == '\n' {
-            grid_y += 1;
-            grid_x = 0;
-        } else {
+    for (grid_y, line) in machine.take_output().lines().enumerate() {
+        for (grid_x, c) in line.chars().enumerate() {
+            let (grid_x, grid_y) = (grid_x as i64, grid_y as i64);
             if c == '.' {
                 grid.insert((grid_x, grid_y), TileType::EMPTY);
             } else if c == '#' || dir_map.contains_key(&c) || c == 'X' {
@@ -207,13 +57,10 @@ fn view_cameras(program: &Vec<i64>) -> (Robot, HashMap<(i64, i64), TileType>) {
             } else {
                 panic!("Unexpected char: {}", c);
             }
-
-            grid_x += 1;
         }
-    };
+    }
 
-    run_program(program, input_fn, output_fn);
-    return (robot_ref.into_inner(), grid_ref.into_inner());
+    return (robot, grid);
 }
 
 fn print_grid(grid: &HashMap<(i64, i64), TileType>, robot: &Robot) {
@@ -423,52 +270,36 @@ fn execute_path(
 
     let video_feed = false;
 
-    let mut line = String::new();
-    line.push_str(top_func.iter().format(",").to_string().as_str());
-    line.push('\n');
-    for (sf_name, sf_val) in sub_funcs {
-        line.push_str(sf_val.iter().format(",").to_string().as_str());
-        line.push('\n');
+    let mut lines = Vec::new();
+    lines.push(top_func.iter().format(",").to_string());
+    for (_, sf_val) in sub_funcs {
+        lines.push(sf_val.iter().format(",").to_string());
     }
-    line.push(if video_feed { 'y' } else { 'n' });
-    line.push('\n');
-
-    let mut inch : Vec<i64> = line.chars().map(|c| c as u32 as i64).collect();
-    let input_fn = || -> Option<i64> {
-        if inch.len() > 0 {
-            return Some(inch.remove(0));
-        } else {
-            return None;
+    lines.push((if video_feed { "y" } else { "n" }).to_string());
+
+    let mut machine = AsciiMachine::new(&live_program);
+    machine.echo = true;
+    match machine.run_lines(lines) {
+        Outcome::Value(dust_count) => {
+            println!("Received dust report: {}", dust_count);
+            return dust_count;
         }
-    };
-
-    let mut dust_count = 0;
-    let output_fn = |i: i64| {
-        if i > 127 || dust_count != 0 {
-            println!("Received dust report: {}", i);
-            dust_count = i;
-        } else {
-            let c = std::char::from_u32(i as u32).unwrap();
-            print!("{}", c);
-        }
-    };
-
-    run_program(&live_program, input_fn, output_fn);
-    return dust_count;
+        outcome => panic!("No dust report: {:?}", outcome),
+    }
 }
 
 fn main() {
     let args : Vec<String> = std::env::args().collect();
     if args[1] == "1" {
         println!("Doing part 1");
-        let program = read_input(&args[2]).unwrap();
+        let program = read_program(&args[2]).unwrap();
         let (robot, grid) = view_cameras(&program);
         print_grid(&grid, &robot);
         let alignments = sum_alignments(&grid);
         println!("Alignments: {}", alignments);
     } else {
         println!("Doing part 2");
-        let program = read_input(&args[2]).unwrap();
+        let program = read_program(&args[2]).unwrap();
         let (robot, grid) = view_cameras(&program);
         let path = calc_path(&grid, &robot);
         let (top, funcs) = compress_path(path, vec!["A", "B", "C"]);
@@ -0,0 +1,537 @@
+use failure::Error;
+use std::collections::HashMap;
+use std::fmt;
+
+// The springdroid only has room for this many instructions
+pub const MAX_INSTRUCTIONS: usize = 15;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub enum Mode {
+    Walk,
+    Run,
+}
+
+impl Mode {
+    // How many ground sensors can be read: A-D when walking, A-I when running
+    pub fn num_sensors(&self) -> usize {
+        return match self {
+            Mode::Walk => 4,
+            Mode::Run => 9,
+        };
+    }
+
+    fn command(&self) -> &'static str {
+        return match self {
+            Mode::Walk => "WALK",
+            Mode::Run => "RUN",
+        };
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub enum Op {
+    And,
+    Or,
+    Not,
+}
+
+// Registers are the letter the droid uses for them: sensors are 'A' onwards,
+// and 'T' and 'J' are the two writable ones
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub struct Instruction {
+    pub op: Op,
+    pub x: char,
+    pub y: char,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.op {
+            Op::And => "AND",
+            Op::Or => "OR",
+            Op::Not => "NOT",
+        };
+        return write!(f, "{} {} {}", op, self.x, self.y);
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub struct Program {
+    pub mode: Mode,
+    pub instructions: Vec<Instruction>,
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instr in &self.instructions {
+            writeln!(f, "{}", instr)?;
+        }
+        return writeln!(f, "{}", self.mode.command());
+    }
+}
+
+fn sensor_name(i: usize) -> char {
+    return (b'A' + i as u8) as char;
+}
+
+fn sensor_names(mode: Mode) -> Vec<char> {
+    return (0..mode.num_sensors()).map(sensor_name).collect();
+}
+
+fn parse_instruction(line: &str, mode: Mode) -> Result<Instruction, Error> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() != 3 || parts[1].len() != 1 || parts[2].len() != 1 {
+        bail!("Bad instruction: {}", line);
+    }
+    let op = match parts[0] {
+        "AND" => Op::And,
+        "OR" => Op::Or,
+        "NOT" => Op::Not,
+        _ => bail!("Unknown op in {}", line),
+    };
+    let x = parts[1].chars().next().unwrap();
+    let y = parts[2].chars().next().unwrap();
+    if !sensor_names(mode).contains(&x) && x != 'T' && x != 'J' {
+        bail!("Can't read {} when doing {}: {}", x, mode.command(), line);
+    }
+    if y != 'T' && y != 'J' {
+        bail!("Can't write to {}: {}", y, line);
+    }
+    return Ok(Instruction { op, x, y });
+}
+
+// Parses springscript lines ending in WALK or RUN, checking that it's
+// something the droid would accept
+pub fn parse_program(lines: &[String]) -> Result<Program, Error> {
+    let (last, body) = match lines.split_last() {
+        Some(split) => split,
+        None => bail!("Empty program"),
+    };
+    let mode = match last.trim() {
+        "WALK" => Mode::Walk,
+        "RUN" => Mode::Run,
+        _ => bail!("Program should end with WALK or RUN, not {}", last),
+    };
+    if body.len() > MAX_INSTRUCTIONS {
+        bail!("Too many instructions: {} (max {})", body.len(), MAX_INSTRUCTIONS);
+    }
+    let instructions = body
+        .iter()
+        .map(|line| parse_instruction(line, mode))
+        .collect::<Result<Vec<Instruction>, Error>>()?;
+    return Ok(Program { mode, instructions });
+}
+
+// Ground is true and holes are false. Past the end is all ground.
+pub type Hull = Vec<bool>;
+
+pub fn parse_hull(line: &str) -> Hull {
+    return line.chars().map(|c| c == '#').collect();
+}
+
+// Pulls the hull out of the droid's report of falling in, which shows it
+// one frame per step, the hull being the first line with any ground on it
+pub fn parse_failure(text: &str) -> Option<Hull> {
+    let idx = text.find("Didn't make it across")?;
+    return text[idx..]
+        .lines()
+        .find(|line| line.contains('#'))
+        .map(parse_hull);
+}
+
+fn is_ground(hull: &Hull, pos: usize) -> bool {
+    return *hull.get(pos).unwrap_or(&true);
+}
+
+// Sensor readings from a spot, with A as the lowest bit
+fn read_sensors(hull: &Hull, pos: usize, mode: Mode) -> u32 {
+    let mut sensors = 0;
+    for i in 0..mode.num_sensors() {
+        if is_ground(hull, pos + 1 + i) {
+            sensors |= 1 << i;
+        }
+    }
+    return sensors;
+}
+
+// Walks (or jumps) the droid along the hull, deciding whether to jump from
+// each spot it stands on; jumping lands four spots ahead. Returns the spot
+// it fell in, if it did.
+fn simulate<F>(hull: &Hull, mode: Mode, mut jumps: F) -> Result<(), usize>
+where
+    F: FnMut(usize, u32) -> bool,
+{
+    let mut pos = 0;
+    while pos < hull.len() {
+        if !hull[pos] {
+            return Err(pos);
+        }
+        if jumps(pos, read_sensors(hull, pos, mode)) {
+            pos += 4;
+        } else {
+            pos += 1;
+        }
+    }
+    return Ok(());
+}
+
+impl Program {
+    pub fn jumps(&self, sensors: u32) -> bool {
+        let mut t = false;
+        let mut j = false;
+        for instr in &self.instructions {
+            let x = match instr.x {
+                'T' => t,
+                'J' => j,
+                c => sensors & (1 << (c as u8 - b'A')) != 0,
+            };
+            let y = if instr.y == 'T' { &mut t } else { &mut j };
+            *y = match instr.op {
+                Op::And => x && *y,
+                Op::Or => x || *y,
+                Op::Not => !x,
+            };
+        }
+        return j;
+    }
+
+    pub fn survives(&self, hull: &Hull) -> Result<(), usize> {
+        return simulate(hull, self.mode, |_, sensors| self.jumps(sensors));
+    }
+}
+
+// A clause is true if any of its sensors reads the value wanted for it. A
+// program's jump decision is built from these, either as an AND of clauses
+// or the NOT of one.
+type Clause = Vec<(usize, bool)>;
+
+fn clause_holds(clause: &Clause, sensors: u32) -> bool {
+    return clause.iter().any(|(i, value)| (sensors & (1 << i) != 0) == *value);
+}
+
+// Instructions that leave the clause in register y. If y is known to be
+// false already (J at the start) ORing can start straight away.
+fn compile_clause(clause: &Clause, y: char, clean: bool) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut push = |op: Op, x: char| instructions.push(Instruction { op, x, y });
+    let neg: Vec<char> = clause.iter().filter(|(_, v)| !*v).map(|(i, _)| sensor_name(*i)).collect();
+    let pos: Vec<char> = clause.iter().filter(|(_, v)| *v).map(|(i, _)| sensor_name(*i)).collect();
+    let mut rest = &pos[..];
+    if neg.len() == 1 {
+        push(Op::Not, neg[0]);
+    } else if neg.len() > 1 {
+        // !a | !b | ... is !(a & b & ...)
+        push(Op::Not, neg[0]);
+        push(Op::Not, y);
+        for x in &neg[1..] {
+            push(Op::And, *x);
+        }
+        push(Op::Not, y);
+    } else if !clean {
+        push(Op::Not, pos[0]);
+        push(Op::Not, y);
+        rest = &pos[1..];
+    }
+    for x in rest {
+        push(Op::Or, *x);
+    }
+    return instructions;
+}
+
+// The clauses ANDed together in J, with T holding each one in turn. The
+// clause that saves the most by going straight into J goes first.
+fn compile_cnf(clauses: &[Clause]) -> Vec<Instruction> {
+    if clauses.is_empty() {
+        return vec![Instruction { op: Op::Not, x: 'J', y: 'J' }];
+    }
+    let combined = |clause: &Clause| -> Vec<Instruction> {
+        if clause.len() == 1 && clause[0].1 {
+            return vec![Instruction { op: Op::And, x: sensor_name(clause[0].0), y: 'J' }];
+        }
+        let mut instructions = compile_clause(clause, 'T', false);
+        instructions.push(Instruction { op: Op::And, x: 'T', y: 'J' });
+        return instructions;
+    };
+    let first = (0..clauses.len())
+        .max_by_key(|idx| {
+            let saving = combined(&clauses[*idx]).len() as i64;
+            return saving - compile_clause(&clauses[*idx], 'J', true).len() as i64;
+        })
+        .unwrap();
+    let mut instructions = compile_clause(&clauses[first], 'J', true);
+    for (idx, clause) in clauses.iter().enumerate() {
+        if idx != first {
+            instructions.extend(combined(clause));
+        }
+    }
+    return instructions;
+}
+
+// Roughly what a clause adds to a program: exact apart from the first one
+fn clause_cost(clause: &Clause) -> usize {
+    if clause.len() == 1 && clause[0].1 {
+        return 1;
+    }
+    return compile_clause(clause, 'T', false).len() + 1;
+}
+
+// Clauses that hold for all of trues but not for the given false reading:
+// they can only use sensors that read the opposite of what the clause wants.
+// Adding a sensor to a clause only makes it hold more often, so only the
+// ones that stop holding for trues if any sensor is taken out are kept.
+fn clauses_against(falsy: u32, trues: &[u32], mode: Mode) -> Vec<Clause> {
+    let n = mode.num_sensors();
+    let valid = |subset: u32| {
+        let clause: Clause = (0..n)
+            .filter(|i| subset & (1 << i) != 0)
+            .map(|i| (i, falsy & (1 << i) == 0))
+            .collect();
+        return trues.iter().all(|t| clause_holds(&clause, *t));
+    };
+    let mut clauses: Vec<Clause> = (1..(1u32 << n))
+        .filter(|subset| valid(*subset))
+        .filter(|subset| (0..n).all(|i| subset & (1 << i) == 0 || !valid(subset & !(1 << i))))
+        .map(|subset| {
+            return (0..n)
+                .filter(|i| subset & (1 << i) != 0)
+                .map(|i| (i, falsy & (1 << i) == 0))
+                .collect();
+        })
+        .collect();
+    clauses.sort_by_key(clause_cost);
+    return clauses;
+}
+
+// Finds the cheapest set of clauses that hold for all of trues and between
+// them rule out all of falses, if there's one within the budget
+fn min_cnf(trues: &[u32], falses: &[u32], mode: Mode, budget: usize) -> Option<Vec<Clause>> {
+    fn cover(
+        falses: &[u32],
+        candidates: &HashMap<u32, Vec<Clause>>,
+        chosen: &mut Vec<Clause>,
+        cost: usize,
+        best: &mut (usize, Option<Vec<Clause>>),
+    ) {
+        let left: Vec<u32> = falses
+            .iter()
+            .filter(|f| chosen.iter().all(|c| clause_holds(c, **f)))
+            .cloned()
+            .collect();
+        if left.is_empty() {
+            if cost < best.0 {
+                *best = (cost, Some(chosen.clone()));
+            }
+            return;
+        }
+        // whichever's hardest to rule out
+        let target = *left.iter().min_by_key(|f| candidates[f].len()).unwrap();
+        for clause in &candidates[&target] {
+            let clause_cost = clause_cost(clause);
+            if cost + clause_cost >= best.0 {
+                break;
+            }
+            chosen.push(clause.clone());
+            cover(&left, candidates, chosen, cost + clause_cost, best);
+            chosen.pop();
+        }
+    }
+
+    let candidates: HashMap<u32, Vec<Clause>> =
+        falses.iter().map(|f| (*f, clauses_against(*f, trues, mode))).collect();
+    // the first clause can be up to two cheaper than clause_cost says
+    let mut best = (budget + 3, None);
+    cover(falses, &candidates, &mut Vec::new(), 0, &mut best);
+    return best.1;
+}
+
+// The shortest program (as far as AND-of-ORs or NOT of that goes) that
+// jumps on every reading in jumps and doesn't on any in walks
+fn synthesize(mode: Mode, jumps: &[u32], walks: &[u32]) -> Option<Program> {
+    if jumps.is_empty() {
+        return Some(Program { mode, instructions: Vec::new() });
+    }
+    let mut options = Vec::new();
+    if let Some(clauses) = min_cnf(jumps, walks, mode, MAX_INSTRUCTIONS) {
+        options.push(compile_cnf(&clauses));
+    }
+    if let Some(clauses) = min_cnf(walks, jumps, mode, MAX_INSTRUCTIONS) {
+        let mut instructions = compile_cnf(&clauses);
+        instructions.push(Instruction { op: Op::Not, x: 'J', y: 'J' });
+        options.push(instructions);
+    }
+    return options
+        .into_iter()
+        .filter(|instructions| instructions.len() <= MAX_INSTRUCTIONS)
+        .min_by_key(|instructions| instructions.len())
+        .map(|instructions| Program { mode, instructions });
+}
+
+// Goes through every way of deciding when to jump that gets across all the
+// hulls, calling found with each until it returns true. A reading always
+// gets the same decision, wherever it comes up.
+fn decide<F>(
+    hulls: &[Hull],
+    mode: Mode,
+    pos: usize,
+    decisions: &mut HashMap<u32, bool>,
+    found: &mut F,
+) -> bool
+where
+    F: FnMut(&HashMap<u32, bool>) -> bool,
+{
+    let hull = match hulls.first() {
+        Some(hull) => hull,
+        None => return found(decisions),
+    };
+    if pos >= hull.len() {
+        return decide(&hulls[1..], mode, 0, decisions, found);
+    }
+    if !hull[pos] {
+        return false;
+    }
+    let sensors = read_sensors(hull, pos, mode);
+    if let Some(jump) = decisions.get(&sensors) {
+        let next = if *jump { pos + 4 } else { pos + 1 };
+        return decide(hulls, mode, next, decisions, found);
+    }
+    for jump in &[false, true] {
+        decisions.insert(sensors, *jump);
+        let next = if *jump { pos + 4 } else { pos + 1 };
+        if decide(hulls, mode, next, decisions, found) {
+            return true;
+        }
+    }
+    decisions.remove(&sensors);
+    return false;
+}
+
+// Finds a program that gets across every one of the hulls, or None if none
+// of the programs it knows how to build fit in the droid. First picks when to jump for
+// each sensor reading that comes up, then looks for the fewest clauses that
+// give those decisions (anything else is free to go either way).
+pub fn search(mode: Mode, hulls: &[Hull]) -> Option<Program> {
+    let mut program = None;
+    decide(hulls, mode, 0, &mut HashMap::new(), &mut |decisions| {
+        let mut jumps: Vec<u32> = decisions.iter().filter(|(_, j)| **j).map(|(s, _)| *s).collect();
+        let mut walks: Vec<u32> = decisions.iter().filter(|(_, j)| !**j).map(|(s, _)| *s).collect();
+        jumps.sort();
+        walks.sort();
+        program = synthesize(mode, &jumps, &walks);
+        return program.is_some();
+    });
+    return program;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        return text.lines().map(|line| line.to_string()).collect();
+    }
+
+    fn parse_err(text: &str) -> String {
+        return parse_program(&lines(text)).unwrap_err().to_string();
+    }
+
+    #[test]
+    fn test_parse_program() {
+        let program = parse_program(&lines("NOT A T\nAND D T\nOR T J\nWALK")).unwrap();
+        assert_eq!(program.mode, Mode::Walk);
+        assert_eq!(
+            program.instructions[1],
+            Instruction { op: Op::And, x: 'D', y: 'T' }
+        );
+        assert_eq!(
+            parse_program(&lines(&program.to_string())).unwrap(),
+            program
+        );
+        // E onwards can only be read when running
+        assert_eq!(
+            parse_program(&lines("OR I J\nRUN")).unwrap().mode,
+            Mode::Run
+        );
+    }
+
+    #[test]
+    fn test_parse_program_errors() {
+        let mut too_long = vec!["NOT A J"; MAX_INSTRUCTIONS];
+        too_long.push("WALK");
+        assert!(parse_program(&lines(&too_long.join("\n"))).is_ok());
+        too_long.insert(0, "NOT B T");
+        assert!(parse_err(&too_long.join("\n")).starts_with("Too many instructions: 16"));
+
+        assert!(parse_err("NOT A B\nWALK").starts_with("Can't write to B"));
+        assert!(parse_err("NOT A A\nWALK").starts_with("Can't write to A"));
+        assert!(parse_err("OR E J\nWALK").starts_with("Can't read E when doing WALK"));
+        assert!(parse_err("XOR A J\nWALK").starts_with("Unknown op"));
+        assert!(parse_err("NOT A J").starts_with("Program should end with WALK or RUN"));
+        assert_eq!(parse_err(""), "Empty program");
+    }
+
+    #[test]
+    fn test_jumps() {
+        // jumps if there's a hole at A or B
+        let program = parse_program(&lines("NOT A T\nNOT B J\nOR T J\nWALK")).unwrap();
+        assert!(!program.jumps(0b1111));
+        assert!(program.jumps(0b1110));
+        assert!(program.jumps(0b1101));
+        assert!(!program.jumps(0b0011));
+    }
+
+    #[test]
+    fn test_survives() {
+        let hole_ahead = parse_program(&lines("NOT A J\nWALK")).unwrap();
+        assert_eq!(hole_ahead.survives(&parse_hull("#####.###")), Ok(()));
+        assert_eq!(hole_ahead.survives(&parse_hull("#...#")), Ok(()));
+        // jumps straight into the second hole
+        assert_eq!(hole_ahead.survives(&parse_hull("#.##.#")), Err(4));
+
+        let never = parse_program(&lines("WALK")).unwrap();
+        assert_eq!(never.survives(&parse_hull("####")), Ok(()));
+        assert_eq!(never.survives(&parse_hull("##.#")), Err(2));
+    }
+
+    #[test]
+    fn test_simulate() {
+        let mut spots = Vec::new();
+        let hull = parse_hull("##.##");
+        let result = simulate(&hull, Mode::Walk, |pos, sensors| {
+            spots.push((pos, sensors));
+            return sensors & 1 == 0;
+        });
+        assert_eq!(result, Ok(()));
+        // past the end reads as ground
+        assert_eq!(spots, vec![(0, 0b1101), (1, 0b1110)]);
+    }
+
+    #[test]
+    fn test_parse_failure() {
+        let report =
+            "\nDidn't make it across:\n\n.................\n@................\n#####.#..########\n";
+        assert_eq!(parse_failure(report), Some(parse_hull("#####.#..########")));
+        assert_eq!(parse_failure("19355645\n"), None);
+    }
+
+    #[test]
+    fn test_search() {
+        assert_eq!(search(Mode::Walk, &[]).unwrap().instructions, vec![]);
+
+        let hulls: Vec<Hull> = [
+            "#####.#..########",
+            "#####..#.########",
+            "#####...#########",
+        ]
+        .iter()
+        .map(|line| parse_hull(line))
+        .collect();
+        let program = search(Mode::Walk, &hulls).unwrap();
+        assert!(program.instructions.len() <= MAX_INSTRUCTIONS);
+        for hull in &hulls {
+            assert_eq!(program.survives(hull), Ok(()));
+        }
+
+        // four holes in a row can't be jumped
+        assert_eq!(search(Mode::Walk, &[parse_hull("#....#")]), None);
+    }
+}
@@ -1,222 +1,203 @@
 #[macro_use] extern crate lazy_static;
-
-use failure::Error;
-use itertools::Itertools;
+use intcode::ascii::{read_script, AsciiMachine, Outcome};
+use intcode::program::read_program;
 use regex::Regex;
-use std::cell::RefCell;
-use std::collections::{BinaryHeap,HashMap,HashSet};
-use std::cmp::Ordering;
-use std::io::{BufReader,BufRead};
-use std::fs::File;
-use std::sync::Mutex;
-
-fn parse_int(val: &str) -> Result<i64, Error> {
-    return Ok(val.parse::<i64>()?);
+use std::collections::HashMap;
+use std::path::Path;
+
+// Anything that goes this long without asking for input is taken to be stuck,
+// even if it hasn't been caught repeating itself
+const MAX_STEPS: usize = 1_000_000;
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+struct Room {
+    name: String,
+    doors: Vec<String>,
+    items: Vec<String>,
 }
 
-fn parse_line(line: &str) -> Result<Vec<i64>, Error> {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r",\s*").unwrap();
+// Finds all the room descriptions in some output. Moving onto the pressure
+// plate with the wrong weight prints two: the plate, and the room you get
+// thrown back into.
+fn parse_rooms(text: &str) -> Vec<Room> {
+    let mut rooms : Vec<Room> = Vec::new();
+    let mut in_items = false;
+    for line in text.lines() {
+        if line.starts_with("== ") && line.ends_with(" ==") {
+            let name = line.trim_matches(|c| c == '=' || c == ' ').to_string();
+            rooms.push(Room { name: name, doors: Vec::new(), items: Vec::new() });
+        } else if line == "Doors here lead:" {
+            in_items = false;
+        } else if line == "Items here:" {
+            in_items = true;
+        } else if line.starts_with("- ") {
+            if let Some(room) = rooms.last_mut() {
+                if in_items {
+                    room.items.push(line[2..].to_string());
+                } else {
+                    room.doors.push(line[2..].to_string());
+                }
+            }
+        }
     }
-    return RE.split(&line).map(|val| parse_int(&val)).collect();
+    return rooms;
 }
 
-fn read_input(file: &str) -> Result<Vec<i64>, Error> {
-    let f = File::open(file)?;
-    let br = BufReader::new(f);
-    let parsed : Result<Vec<Vec<i64>>, Error> = br.lines().map(|line| parse_line(&line?)).collect();
-    return Ok(parsed?.into_iter().flatten().collect());
+fn opposite(dir: &str) -> &'static str {
+    return match dir {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        "west" => "east",
+        _ => panic!("Bad direction: {}", dir),
+    };
 }
 
-enum ExitType {
-    Finish99,
-    InputAbort,
+// Sends a command and returns what got printed, or None if the game halted
+// or got stuck in a loop
+fn command(machine: &mut AsciiMachine, cmd: &str) -> Option<String> {
+    machine.send_line(cmd);
+    return match machine.run_or_loop(MAX_STEPS) {
+        Some(Outcome::NeedsInput) => Some(machine.take_output()),
+        _ => None,
+    };
 }
 
-fn run_program(program_init: &Vec<i64>, mut input: impl FnMut() -> Option<i64>, mut output: impl FnMut(i64)) -> ExitType {
-    let relative_base = -99;
+struct Explorer {
+    rooms: HashMap<String, Room>,
+    // doors to go through to get to each room from the start
+    paths: HashMap<String, Vec<String>>,
+    inventory: Vec<String>,
+    deadly: Vec<String>,
+    // the security checkpoint, and the door from it to the pressure plate
+    checkpoint: Option<(String, String)>,
+}
 
-    let read_idx = |i: i64, program: &HashMap<i64, i64>| -> i64 {
-        if i < 0 {
-            panic!("Index {} outside of program bound ({})", i, program.len());
+impl Explorer {
+    // Tries the item out on a copy of the game: it's deadly if picking it up
+    // ends the game or never finishes, or if we can't leave the room with it
+    fn is_deadly(&self, machine: &AsciiMachine, room: &Room, item: &str) -> bool {
+        let mut test = machine.clone();
+        if command(&mut test, &format!("take {}", item)).is_none() {
+            return true;
         }
-        match program.get(&i) {
-            Some(&v) => { return v; },
-            None => { return 0; },
+        return match command(&mut test, &room.doors[0]) {
+            Some(text) => parse_rooms(&text).is_empty(),
+            None => true,
         };
-    };
+    }
 
-    let write_idx = |i: i64, value: i64, program: &mut HashMap<i64, i64>| {
-        if i < 0 {
-            panic!("Index {} outside of program bound ({})", i, program.len());
+    // Depth-first, picking up everything safe along the way, and coming
+    // back to where we started from when done
+    fn explore(&mut self, machine: &mut AsciiMachine, room: Room, path: &mut Vec<String>) {
+        self.rooms.insert(room.name.clone(), room.clone());
+        self.paths.insert(room.name.clone(), path.clone());
+        for item in &room.items {
+            if self.is_deadly(machine, &room, item) {
+                self.deadly.push(item.clone());
+            } else {
+                command(machine, &format!("take {}", item)).unwrap();
+                self.inventory.push(item.clone());
+            }
         }
-        program.insert(i, value);
-    };
 
-    let resolve_idx = |pc: i64, num: i64, modes: i64, program: &HashMap<i64, i64>| -> i64 {
-        let i = pc + num;
-        let mut flag;
-        if num > 0 {
-            flag = modes;
-            for _ in 0..num-1 {
-                flag /= 10;
+        for door in &room.doors {
+            let text = command(machine, door).unwrap();
+            let next = parse_rooms(&text).pop().expect("Went through a door to nowhere");
+            if next.name == room.name {
+                self.checkpoint = Some((room.name.clone(), door.clone()));
+                continue;
             }
-            flag %= 10;
-        } else {
-            flag = 1;
-        }
-        if flag == 0 {
-            return read_idx(i, program);
-        } else if flag == 1 {
-            return i;
-        } else if flag == 2 {
-            return read_idx(i, program) + program[&relative_base];
-        } else {
-            panic!("Bad flag at {}: {}", i, flag);
+            if !self.rooms.contains_key(&next.name) {
+                path.push(door.clone());
+                self.explore(machine, next, path);
+                path.pop();
+            }
+            command(machine, opposite(door)).unwrap();
         }
-    };
-
-    let get = |pc: i64, num: i64, modes: i64, program: &HashMap<i64, i64>| -> i64 {
-        return read_idx(resolve_idx(pc, num, modes, program), program);
-    };
+    }
+}
 
-    let set = |pc: i64, num: i64, modes: i64, value: i64, program: &mut HashMap<i64, i64>| {
-        return write_idx(resolve_idx(pc, num, modes, program), value, program);
+// Maps the whole ship, carrying everything that's safe to the checkpoint,
+// and then tries every combination of items on the pressure plate
+fn solve_game(program: &Vec<i64>) -> String {
+    let mut machine = AsciiMachine::new(program);
+    machine.run();
+    let start = parse_rooms(&machine.take_output()).pop().unwrap();
+    let mut explorer = Explorer {
+        rooms: HashMap::new(),
+        paths: HashMap::new(),
+        inventory: Vec::new(),
+        deadly: Vec::new(),
+        checkpoint: None,
     };
-
-    let mut program : HashMap<i64, i64> = HashMap::new();
-    for i in 0..program_init.len() {
-        program.insert(i as i64, program_init[i]);
+    explorer.explore(&mut machine, start, &mut Vec::new());
+    println!("Found {} rooms", explorer.rooms.len());
+    println!("Carrying: {}", explorer.inventory.join(", "));
+    println!("Left behind: {}", explorer.deadly.join(", "));
+
+    let (checkpoint, door) = explorer.checkpoint.expect("Never found the pressure plate");
+    for dir in &explorer.paths[&checkpoint] {
+        command(&mut machine, dir).unwrap();
     }
-    program.insert(relative_base, 0);
 
-    let mut pc : i64 = 0;
-    loop {
-        if pc >= program.len() as i64 || pc < 0 {
-            panic!("PC {} outside of program bound ({})", pc, program.len());
-        }
-        let opcode = get(pc, 0, 0, &program) % 100;
-        let modes = get(pc, 0, 0, &program) / 100;
-        // println!("Executing at pc={} opcode={}, modes={}", pc, opcode, modes);
-        if opcode == 1 {
-            let value = get(pc, 1, modes, &program) + get(pc, 2, modes, &program);
-            set(pc, 3, modes, value, &mut program);
-            pc += 4;
-        } else if opcode == 2 {
-            let value = get(pc, 1, modes, &program) * get(pc, 2, modes, &program);
-            set(pc, 3, modes, value, &mut program);
-            pc += 4;
-        } else if opcode == 3 {
-            let value = input();
-            match value {
-                Some(v) => {
-                    set(pc, 1, modes, v, &mut program);
-                    pc += 2;
-                },
-                None => {
-                    return ExitType::InputAbort;
-                }
-            }
-        } else if opcode == 4 {
-            output(get(pc, 1, modes, &program));
-            pc += 2;
-        } else if opcode == 5 || opcode == 6 {
-            if (get(pc, 1, modes, &program) == 0) == (opcode == 6) {
-                pc = get(pc, 2, modes, &program);
-            } else {
-                pc += 3;
+    lazy_static! {
+        static ref CODE_RE: Regex = Regex::new(r"typing (\d+) on the keypad").unwrap();
+    }
+    for keep in powerset(&explorer.inventory) {
+        let mut attempt = machine.clone();
+        for item in &explorer.inventory {
+            if !keep.contains(&item) {
+                command(&mut attempt, &format!("drop {}", item)).unwrap();
             }
-        } else if opcode == 7 {
-            let value = if get(pc, 1, modes, &program) < get(pc, 2, modes, &program) { 1 } else { 0 };
-            set(pc, 3, modes, value, &mut program);
-            pc += 4;
-        } else if opcode == 8 {
-            let value = if get(pc, 1, modes, &program) == get(pc, 2, modes, &program) { 1 } else { 0 };
-            set(pc, 3, modes, value, &mut program);
-            pc += 4;
-        } else if opcode == 9 {
-            let value = get(pc, 1, modes, &program);
-            *program.get_mut(&relative_base).unwrap() += value;
-            pc += 2;
-        } else if opcode == 99 {
-            // println!("Saw 99, exiting");
-            return ExitType::Finish99;
-        } else {
-            panic!("Unknown opcode at {}: {}", pc, get(pc, 0, 0, &program));
+        }
+        attempt.send_line(&door);
+        if attempt.run_or_loop(MAX_STEPS) != Some(Outcome::Halted) {
+            continue;
+        }
+        let text = attempt.take_output();
+        if let Some(caps) = CODE_RE.captures(&text) {
+            println!("Holding: {}", keep.iter().map(|s| s.as_str()).collect::<Vec<&str>>().join(", "));
+            return caps[1].to_string();
         }
     }
+    panic!("No combination of items got through");
 }
 
-fn play_game(program: &Vec<i64>) {
-    let mut line : Vec<i64> = Vec::new();
-    let input_fn = || -> Option<i64> {
-        if line.len() > 0 {
-            return Some(line.remove(0));
-        } else {
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input);
-            let inv : Vec<String> = vec![
-                "prime number", "mutex", "manifold", "cake", "coin", "dehydrated water", "fuel cell", "candy cane"
-            ].into_iter().map(|s| s.to_string()).collect();
-            if input == "xyzzy\n" {
-                input = brute_force(&inv);
-            }
-            if input == "plugh\n" {
-                input = gather(&inv);
-            }
-            for ch in input.trim().chars() {
-                line.push(ch as i64);
+// Plays interactively from stdin, after first replaying the script if one is
+// given. Typing "plugh" replays gather.txt from next to the input, and
+// "xyzzy" tries every combination of items on the pressure plate.
+fn play_game(program: &Vec<i64>, input_file: &str, script: Option<&str>) {
+    let inv : Vec<String> = vec![
+        "prime number", "mutex", "manifold", "cake", "coin", "dehydrated water", "fuel cell", "candy cane"
+    ].into_iter().map(|s| s.to_string()).collect();
+    let gather_file = Path::new(input_file).with_file_name("gather.txt");
+
+    let mut machine = AsciiMachine::new(program);
+    machine.echo = true;
+    if let Some(script) = script {
+        machine.run_script(script).unwrap();
+    }
+    loop {
+        match machine.run() {
+            Outcome::NeedsInput => {}
+            Outcome::Halted => return,
+            Outcome::Value(rc) => {
+                println!("Received return code: {}", rc);
+                return;
             }
-            line.push('\n' as i64);
-            return Some(line.remove(0));
         }
-    };
-
-    let output_fn = |i: i64| {
-        if i > 127 {
-            println!("Received return code: {}", i);
-        } else {
-            let c = std::char::from_u32(i as u32).unwrap();
-            print!("{}", c);
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input).unwrap() == 0 {
+            return;
         }
-    };
-
-    run_program(&program, input_fn, output_fn);
-}
-
-fn gather(inv: &Vec<String>) -> String {
-    let mut cmds : Vec<String> = vec![
-        "south",
-        "south",
-        "take manifold",
-        "north",
-        "take fuel cell",
-        "north",
-        "north",
-        "take candy cane",
-        "south",
-        "west",
-        "take mutex",
-        "south",
-        "south",
-        "take coin",
-        "west",
-        "south",
-        "take prime number",
-        "north",
-        "take dehydrated water",
-        "east",
-        "north",
-        "east",
-        "take cake",
-        "north",
-        "west",
-        "south",
-    ].into_iter().map(|s| s.to_string()).collect();
-    for item in inv {
-        cmds.push(format!("drop {}", item));
+        let lines = match input.trim() {
+            "xyzzy" => brute_force(&inv),
+            "plugh" => read_script(gather_file.to_str().unwrap()).unwrap(),
+            line => vec![line.to_string()],
+        };
+        machine.send_lines(lines);
     }
-    return cmds.iter().format("\n").to_string();
 }
 
 // https://stackoverflow.com/a/40719103
@@ -228,21 +209,20 @@ fn powerset<T>(s: &[T]) -> Vec<Vec<&T>> {
      }).collect()
 }
 
-fn brute_force(inv: &Vec<String>) -> String {
-    let mut ret = String::new();
+fn brute_force(inv: &Vec<String>) -> Vec<String> {
+    let mut ret = Vec::new();
     for ss in powerset(inv) {
         if ss.len() == 0 {
             continue;
         }
-        let mut drop = String::new();
+        let mut drop = Vec::new();
         for element in ss {
-            ret += &format!("take {}\n", element);
-            drop += &format!("drop {}\n", element);
+            ret.push(format!("take {}", element));
+            drop.push(format!("drop {}", element));
         }
-        ret.push_str("west\n");
-        ret += &drop;
+        ret.push("west".to_string());
+        ret.extend(drop);
     }
-    println!("XXX: {}", ret);
     return ret;
 }
 
@@ -250,8 +230,13 @@ fn main() {
     let args : Vec<String> = std::env::args().collect();
     if args[1] == "1" {
         println!("Doing part 1");
-        let program = read_input(&args[2]).unwrap();
-        play_game(&program);
+        let program = read_program(&args[2]).unwrap();
+        if args.len() > 3 && args[3] == "play" {
+            play_game(&program, &args[2], args.get(4).map(|s| s.as_str()));
+        } else {
+            let code = solve_game(&program);
+            println!("Airlock code: {}", code);
+        }
     } else {
         println!("Doing part 2");
     }
@@ -0,0 +1,304 @@
+use failure::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use crate::machine::{Machine, Status};
+
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub enum TranscriptLine {
+    Input(String),
+    Output(String),
+}
+
+// Everything sent to and printed by a machine, a line at a time. Displays
+// with "> " in front of the lines that were input, like a terminal would.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Hash)]
+pub struct Transcript {
+    pub lines: Vec<TranscriptLine>,
+}
+
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                TranscriptLine::Input(text) => writeln!(f, "> {}", text)?,
+                TranscriptLine::Output(text) => writeln!(f, "{}", text)?,
+            }
+        }
+        return Ok(());
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub enum Outcome {
+    // the machine wants another line of input
+    NeedsInput,
+    // halted having only printed text
+    Halted,
+    // halted after outputting a value too big to be a character, which is
+    // how programs usually report an answer
+    Value(i64),
+}
+
+// Wraps a machine that talks in ASCII: input is sent as lines of text, and
+// output is collected as text until someone asks for it.
+#[derive(Clone, Debug)]
+pub struct AsciiMachine {
+    machine: Machine,
+    // output text that hasn't been asked for yet
+    pending: String,
+    // output since the last newline, for the transcript
+    partial: String,
+    value: Option<i64>,
+    pub transcript: Transcript,
+    // print output as it's produced
+    pub echo: bool,
+}
+
+impl AsciiMachine {
+    pub fn new(program: &[i64]) -> AsciiMachine {
+        return AsciiMachine::from_machine(Machine::new(program));
+    }
+
+    pub fn from_machine(machine: Machine) -> AsciiMachine {
+        return AsciiMachine {
+            machine,
+            pending: String::new(),
+            partial: String::new(),
+            value: None,
+            transcript: Transcript::default(),
+            echo: false,
+        };
+    }
+
+    pub fn machine(&self) -> &Machine {
+        return &self.machine;
+    }
+
+    pub fn machine_mut(&mut self) -> &mut Machine {
+        return &mut self.machine;
+    }
+
+    // The last non-ASCII value output, if there's been one
+    pub fn value(&self) -> Option<i64> {
+        return self.value;
+    }
+
+    pub fn send_line(&mut self, line: &str) {
+        self.flush_partial();
+        self.machine.push_inputs(line.chars().map(|c| c as i64));
+        self.machine.push_input('\n' as i64);
+        self.transcript
+            .lines
+            .push(TranscriptLine::Input(line.to_string()));
+    }
+
+    pub fn send_lines<I>(&mut self, lines: I)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        for line in lines {
+            self.send_line(line.as_ref());
+        }
+    }
+
+    // Runs until the machine halts or wants input it doesn't have
+    pub fn run(&mut self) -> Outcome {
+        let status = self.machine.run_until_input();
+        self.collect_outputs();
+        return self.outcome(status);
+    }
+
+    // Like run, but returns None if the machine gets stuck in a loop (see
+    // Machine::run_until_input_or_loop)
+    pub fn run_or_loop(&mut self, max_steps: usize) -> Option<Outcome> {
+        let status = self.machine.run_until_input_or_loop(max_steps);
+        self.collect_outputs();
+        return status.map(|status| self.outcome(status));
+    }
+
+    // Runs, sending the next line whenever the machine asks for one. Returns
+    // NeedsInput if the lines run out first.
+    pub fn run_lines<I>(&mut self, lines: I) -> Outcome
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut lines = lines.into_iter();
+        loop {
+            let outcome = self.run();
+            if outcome != Outcome::NeedsInput {
+                return outcome;
+            }
+            match lines.next() {
+                Some(line) => self.send_line(line.as_ref()),
+                None => return outcome,
+            }
+        }
+    }
+
+    pub fn run_script(&mut self, file: &str) -> Result<Outcome, Error> {
+        let script = read_script(file)?;
+        return Ok(self.run_lines(script));
+    }
+
+    // All the output text that hasn't been taken yet, without running
+    pub fn take_output(&mut self) -> String {
+        return std::mem::take(&mut self.pending);
+    }
+
+    // The next full line of output (without the newline), running the
+    // machine if needed. Returns None if it stops before finishing a line;
+    // whatever it did print is still there for take_output.
+    pub fn read_line(&mut self) -> Option<String> {
+        loop {
+            if let Some(idx) = self.pending.find('\n') {
+                let line = self.pending[..idx].to_string();
+                self.pending.drain(..=idx);
+                return Some(line);
+            }
+            if self.machine.step() != Status::Running {
+                self.collect_outputs();
+                return None;
+            }
+            self.collect_outputs();
+        }
+    }
+
+    fn collect_outputs(&mut self) {
+        while let Some(value) = self.machine.take_output() {
+            if !(0..=127).contains(&value) {
+                self.value = Some(value);
+                continue;
+            }
+            let c = value as u8 as char;
+            if self.echo {
+                print!("{}", c);
+            }
+            self.pending.push(c);
+            if c == '\n' {
+                let line = std::mem::take(&mut self.partial);
+                self.transcript.lines.push(TranscriptLine::Output(line));
+            } else {
+                self.partial.push(c);
+            }
+        }
+    }
+
+    // A prompt usually doesn't end in a newline, but it should still get its
+    // own line in the transcript
+    fn flush_partial(&mut self) {
+        if !self.partial.is_empty() {
+            let line = std::mem::take(&mut self.partial);
+            self.transcript.lines.push(TranscriptLine::Output(line));
+        }
+    }
+
+    fn outcome(&mut self, status: Status) -> Outcome {
+        return match status {
+            Status::Halted => {
+                self.flush_partial();
+                match self.value {
+                    Some(value) => Outcome::Value(value),
+                    None => Outcome::Halted,
+                }
+            }
+            _ => Outcome::NeedsInput,
+        };
+    }
+}
+
+// Reads a file of input lines to send to an ASCII program. Anything after a
+// '#' is a comment, and lines that are blank after removing comments are
+// skipped.
+pub fn read_script(file: &str) -> Result<Vec<String>, Error> {
+    let f = File::open(file)?;
+    let br = BufReader::new(f);
+    let mut script = Vec::new();
+    for line in br.lines() {
+        let line = line?;
+        let line = match line.find('#') {
+            Some(idx) => &line[..idx],
+            None => &line[..],
+        };
+        let line = line.trim();
+        if !line.is_empty() {
+            script.push(line.to_string());
+        }
+    }
+    return Ok(script);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // prints '>', then echoes characters until it gets a 'q', when it outputs
+    // 1000 and halts
+    const ECHO: [i64; 19] = [
+        104, 62, 3, 100, 1008, 100, 113, 101, 1005, 101, 16, 4, 100, 1105, 1, 2, 104, 1000, 99,
+    ];
+
+    #[test]
+    fn test_transcript() {
+        let mut machine = AsciiMachine::new(&ECHO);
+        assert_eq!(machine.run(), Outcome::NeedsInput);
+        machine.send_line("hi");
+        assert_eq!(machine.run(), Outcome::NeedsInput);
+        assert_eq!(machine.take_output(), ">hi\n");
+        assert_eq!(machine.value(), None);
+
+        machine.send_line("q");
+        assert_eq!(machine.run(), Outcome::Value(1000));
+        assert_eq!(machine.value(), Some(1000));
+        assert_eq!(
+            machine.transcript.lines,
+            vec![
+                TranscriptLine::Output(">".to_string()),
+                TranscriptLine::Input("hi".to_string()),
+                TranscriptLine::Output("hi".to_string()),
+                TranscriptLine::Input("q".to_string()),
+            ]
+        );
+        assert_eq!(machine.transcript.to_string(), ">\n> hi\nhi\n> q\n");
+    }
+
+    #[test]
+    fn test_run_lines() {
+        let mut machine = AsciiMachine::new(&ECHO);
+        assert_eq!(machine.run_lines(vec!["ab", "cd"]), Outcome::NeedsInput);
+        assert_eq!(machine.run_lines(vec!["q"]), Outcome::Value(1000));
+        assert_eq!(machine.take_output(), ">ab\ncd\n");
+    }
+
+    #[test]
+    fn test_read_line() {
+        let mut machine = AsciiMachine::new(&ECHO);
+        machine.send_lines(vec!["one", "two"]);
+        assert_eq!(machine.read_line(), Some(">one".to_string()));
+        assert_eq!(machine.read_line(), Some("two".to_string()));
+        // it needs more input before it can finish another line
+        assert_eq!(machine.read_line(), None);
+
+        // prints "A\nB" and halts
+        let mut machine = AsciiMachine::new(&[104, 65, 104, 10, 104, 66, 99]);
+        assert_eq!(machine.read_line(), Some("A".to_string()));
+        assert_eq!(machine.read_line(), None);
+        assert_eq!(machine.take_output(), "B");
+        assert_eq!(machine.run(), Outcome::Halted);
+    }
+
+    #[test]
+    fn test_read_script() {
+        let path = std::env::temp_dir().join(format!("ascii_script_{}.txt", std::process::id()));
+        let text = "# a comment\n\nNOT A J  # jump over holes\n   \n  WALK\n";
+        std::fs::write(&path, text).unwrap();
+        let script = read_script(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(script.unwrap(), vec!["NOT A J", "WALK"]);
+
+        assert!(read_script("/nonexistent/script.txt").is_err());
+    }
+}
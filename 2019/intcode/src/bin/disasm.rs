@@ -0,0 +1,9 @@
+use intcode::disasm::disassemble;
+use intcode::program::read_program;
+
+// Usage: disasm <program file>
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let program = read_program(&args[1]).unwrap();
+    print!("{}", disassemble(&program));
+}
@@ -0,0 +1,28 @@
+use intcode::machine::Machine;
+use intcode::program::read_program;
+use intcode::trace::{StopReason, Tracer};
+
+// Usage: trace <program file> [input values...]
+// Prints every instruction executed until the program halts or runs out of
+// input, along with any outputs.
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let program = read_program(&args[1]).unwrap();
+    let mut machine = Machine::new(&program);
+    machine.push_inputs(args[2..].iter().map(|a| a.parse::<i64>().unwrap()));
+
+    let mut tracer = Tracer::new();
+    tracer.verbose = true;
+    loop {
+        match tracer.run_until_output(&mut machine) {
+            Ok(value) => println!("Output: {}", value),
+            Err(StopReason::Halted) => break,
+            Err(StopReason::WaitingForInput) => {
+                println!("Out of input at pc={}", machine.pc());
+                break;
+            }
+            Err(reason) => panic!("Unexpected stop: {:?}", reason),
+        }
+    }
+    println!("Executed {} instructions", tracer.steps);
+}
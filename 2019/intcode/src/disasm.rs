@@ -0,0 +1,235 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::opcode::{decode, Instruction, Mode, Opcode};
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Entry {
+    Code {
+        addr: i64,
+        instr: Instruction,
+        params: Vec<i64>,
+    },
+    Data {
+        addr: i64,
+        values: Vec<i64>,
+    },
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Listing {
+    pub entries: Vec<Entry>,
+    // addresses that some instruction jumps to directly
+    pub jump_targets: BTreeSet<i64>,
+}
+
+// Number of values shown per line in a data region
+const DATA_WIDTH: usize = 8;
+
+fn decode_at(program: &[i64], addr: i64) -> Option<Instruction> {
+    if addr < 0 || addr as usize >= program.len() {
+        return None;
+    }
+    let instr = decode(program[addr as usize])?;
+    if (addr + instr.size()) as usize > program.len() {
+        return None;
+    }
+    return Some(instr);
+}
+
+// Static reachability from address 0. Jumps with an immediate target are
+// followed, and jumps whose condition is an immediate don't fall through if
+// they're always taken. Jumps through memory can't be followed, but the
+// usual calling convention stores the return address with an immediate
+// ADD/MUL before jumping, so those constants are treated as code if they
+// land just past a jump we've already seen.
+pub fn find_code(program: &[i64]) -> (BTreeMap<i64, Instruction>, BTreeSet<i64>) {
+    let mut code = BTreeMap::new();
+    let mut targets = BTreeSet::new();
+    let mut constants = BTreeSet::new();
+    // constants already pushed as return addresses, so one that doesn't decode
+    // isn't pushed again on every pass
+    let mut tried = BTreeSet::new();
+    let mut working = vec![0];
+
+    loop {
+        while let Some(addr) = working.pop() {
+            if code.contains_key(&addr) {
+                continue;
+            }
+            let instr = match decode_at(program, addr) {
+                Some(instr) => instr,
+                None => continue,
+            };
+            code.insert(addr, instr);
+            let param = |num: i64| program[(addr + num) as usize];
+
+            match instr.opcode {
+                Opcode::Halt => {}
+                Opcode::JumpTrue | Opcode::JumpFalse => {
+                    let mut always = false;
+                    let mut never = false;
+                    if instr.modes[0] == Mode::Immediate {
+                        always = (param(1) != 0) == (instr.opcode == Opcode::JumpTrue);
+                        never = !always;
+                    }
+                    if instr.modes[1] == Mode::Immediate && !never {
+                        targets.insert(param(2));
+                        working.push(param(2));
+                    }
+                    if !always {
+                        working.push(addr + instr.size());
+                    }
+                }
+                Opcode::Add | Opcode::Mul => {
+                    if instr.modes[0] == Mode::Immediate && instr.modes[1] == Mode::Immediate {
+                        if instr.opcode == Opcode::Add {
+                            constants.insert(param(1) + param(2));
+                        } else {
+                            constants.insert(param(1) * param(2));
+                        }
+                    }
+                    working.push(addr + instr.size());
+                }
+                _ => {
+                    working.push(addr + instr.size());
+                }
+            }
+        }
+
+        for constant in &constants {
+            if code.contains_key(constant) || tried.contains(constant) {
+                continue;
+            }
+            let is_return = match code.get(&(constant - 3)) {
+                Some(instr) => {
+                    instr.opcode == Opcode::JumpTrue || instr.opcode == Opcode::JumpFalse
+                }
+                None => false,
+            };
+            if is_return {
+                tried.insert(*constant);
+                working.push(*constant);
+            }
+        }
+        if working.is_empty() {
+            return (code, targets);
+        }
+    }
+}
+
+pub fn disassemble(program: &[i64]) -> Listing {
+    let (code, jump_targets) = find_code(program);
+    let mut entries = Vec::new();
+    let mut data: Option<(i64, Vec<i64>)> = None;
+
+    let mut addr = 0;
+    while (addr as usize) < program.len() {
+        match code.get(&addr) {
+            Some(instr) => {
+                if let Some((start, values)) = data.take() {
+                    entries.push(Entry::Data { addr: start, values });
+                }
+                let params = program[(addr + 1) as usize..(addr + instr.size()) as usize].to_vec();
+                entries.push(Entry::Code { addr, instr: *instr, params });
+                addr += instr.size();
+            }
+            None => {
+                match data.as_mut() {
+                    Some((_, values)) => values.push(program[addr as usize]),
+                    None => data = Some((addr, vec![program[addr as usize]])),
+                }
+                addr += 1;
+            }
+        }
+    }
+    if let Some((start, values)) = data.take() {
+        entries.push(Entry::Data { addr: start, values });
+    }
+
+    return Listing { entries, jump_targets };
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            match entry {
+                Entry::Code { addr, instr, params } => {
+                    if self.jump_targets.contains(addr) {
+                        writeln!(f, "L{}:", addr)?;
+                    }
+                    let mut args = Vec::new();
+                    for (idx, param) in params.iter().enumerate() {
+                        let is_target = idx == 1
+                            && (instr.opcode == Opcode::JumpTrue
+                                || instr.opcode == Opcode::JumpFalse)
+                            && instr.modes[1] == Mode::Immediate;
+                        if is_target {
+                            args.push(format!("L{}", param));
+                        } else {
+                            args.push(instr.modes[idx].format_param(*param));
+                        }
+                    }
+                    writeln!(
+                        f,
+                        "{:>6}  {:<4} {}",
+                        addr,
+                        instr.opcode.mnemonic(),
+                        args.join(", ")
+                    )?;
+                }
+                Entry::Data { addr, values } => {
+                    let plural = if values.len() == 1 { "" } else { "s" };
+                    writeln!(
+                        f,
+                        "{:>6}  ; {} unreached value{}",
+                        addr,
+                        values.len(),
+                        plural
+                    )?;
+                    for (idx, chunk) in values.chunks(DATA_WIDTH).enumerate() {
+                        let strs: Vec<String> = chunk.iter().map(|v| v.to_string()).collect();
+                        writeln!(
+                            f,
+                            "{:>6}  DATA {}",
+                            *addr + (idx * DATA_WIDTH) as i64,
+                            strs.join(", ")
+                        )?;
+                    }
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_untried_constant_does_not_loop() {
+        // 7 is stored with an immediate ADD and sits just past the JF at 4, but
+        // decodes as nothing, so it should be tried once and left as data
+        let program = vec![1101, 0, 7, 20, 1106, 0, 8, 0, 99];
+        let (code, targets) = find_code(&program);
+        assert_eq!(code.keys().copied().collect::<Vec<i64>>(), vec![0, 4, 8]);
+        assert_eq!(targets.into_iter().collect::<Vec<i64>>(), vec![8]);
+
+        let listing = disassemble(&program);
+        assert_eq!(listing.entries[2], Entry::Data { addr: 7, values: vec![0] });
+    }
+
+    #[test]
+    fn test_return_address_is_code() {
+        // stores 7 as the return address, jumps to 9, which jumps back through
+        // memory, so 7 is only found by the calling convention
+        let mut program = vec![1101, 0, 7, 20, 1105, 1, 9, 99, 0, 6, 21, 20, 99];
+        program.resize(22, 0);
+        let (code, _) = find_code(&program);
+        assert_eq!(
+            code.keys().copied().collect::<Vec<i64>>(),
+            vec![0, 4, 7, 9, 12]
+        );
+    }
+}
@@ -0,0 +1,8 @@
+pub mod ascii;
+pub mod disasm;
+pub mod machine;
+pub mod memory;
+pub mod network;
+pub mod opcode;
+pub mod program;
+pub mod trace;
@@ -0,0 +1,390 @@
+use std::collections::VecDeque;
+
+use crate::memory::Memory;
+use crate::opcode::{decode, Instruction, Mode, Opcode};
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub enum Status {
+    Running,
+    WaitingForInput,
+    Halted,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Machine {
+    memory: Memory,
+    pc: i64,
+    relative_base: i64,
+    status: Status,
+    inputs: VecDeque<i64>,
+    outputs: VecDeque<i64>,
+}
+
+impl Machine {
+    pub fn new(program: &[i64]) -> Machine {
+        return Machine {
+            memory: Memory::new(program),
+            pc: 0,
+            relative_base: 0,
+            status: Status::Running,
+            inputs: VecDeque::new(),
+            outputs: VecDeque::new(),
+        };
+    }
+
+    pub fn status(&self) -> Status {
+        return self.status;
+    }
+
+    pub fn is_halted(&self) -> bool {
+        return self.status == Status::Halted;
+    }
+
+    pub fn pc(&self) -> i64 {
+        return self.pc;
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        return self.relative_base;
+    }
+
+    pub fn memory(&self) -> &Memory {
+        return &self.memory;
+    }
+
+    // A separate machine that starts from this one's current state. This is
+    // cheap, since memory is only copied a page at a time as either machine
+    // writes to it.
+    pub fn fork(&self) -> Machine {
+        return self.clone();
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        return Snapshot { machine: self.clone() };
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        *self = snapshot.machine.clone();
+    }
+
+    // Memory past the end of the program reads as 0
+    pub fn read(&self, addr: i64) -> i64 {
+        if addr < 0 {
+            panic!(
+                "Index {} outside of program bound ({})",
+                addr,
+                self.memory.len()
+            );
+        }
+        return self.memory.read(addr as usize);
+    }
+
+    pub fn write(&mut self, addr: i64, value: i64) {
+        if addr < 0 {
+            panic!(
+                "Index {} outside of program bound ({})",
+                addr,
+                self.memory.len()
+            );
+        }
+        self.memory.write(addr as usize, value);
+    }
+
+    pub fn push_input(&mut self, value: i64) {
+        self.inputs.push_back(value);
+    }
+
+    pub fn push_inputs<I>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = i64>,
+    {
+        self.inputs.extend(values);
+    }
+
+    pub fn has_input(&self) -> bool {
+        return !self.inputs.is_empty();
+    }
+
+    pub fn take_output(&mut self) -> Option<i64> {
+        return self.outputs.pop_front();
+    }
+
+    pub fn take_outputs(&mut self) -> Vec<i64> {
+        return self.outputs.drain(..).collect();
+    }
+
+    pub fn current_instruction(&self) -> Option<Instruction> {
+        return decode(self.read(self.pc));
+    }
+
+    // Executes a single instruction. If that instruction is an input and
+    // nothing is queued, the pc doesn't move and the status becomes
+    // WaitingForInput; pushing an input and stepping again will resume.
+    pub fn step(&mut self) -> Status {
+        if self.status == Status::Halted {
+            return self.status;
+        }
+
+        let value = self.read(self.pc);
+        let instr = match decode(value) {
+            Some(instr) => instr,
+            None => panic!("Unknown opcode at {}: {}", self.pc, value),
+        };
+
+        match instr.opcode {
+            Opcode::Add => {
+                let value = self.get(&instr, 1) + self.get(&instr, 2);
+                self.set(&instr, 3, value);
+                self.pc += instr.size();
+            }
+            Opcode::Mul => {
+                let value = self.get(&instr, 1) * self.get(&instr, 2);
+                self.set(&instr, 3, value);
+                self.pc += instr.size();
+            }
+            Opcode::In => match self.inputs.pop_front() {
+                Some(value) => {
+                    self.set(&instr, 1, value);
+                    self.pc += instr.size();
+                }
+                None => {
+                    self.status = Status::WaitingForInput;
+                    return self.status;
+                }
+            },
+            Opcode::Out => {
+                let value = self.get(&instr, 1);
+                self.outputs.push_back(value);
+                self.pc += instr.size();
+            }
+            Opcode::JumpTrue | Opcode::JumpFalse => {
+                if (self.get(&instr, 1) == 0) == (instr.opcode == Opcode::JumpFalse) {
+                    self.pc = self.get(&instr, 2);
+                } else {
+                    self.pc += instr.size();
+                }
+            }
+            Opcode::LessThan => {
+                let value = if self.get(&instr, 1) < self.get(&instr, 2) {
+                    1
+                } else {
+                    0
+                };
+                self.set(&instr, 3, value);
+                self.pc += instr.size();
+            }
+            Opcode::Equals => {
+                let value = if self.get(&instr, 1) == self.get(&instr, 2) {
+                    1
+                } else {
+                    0
+                };
+                self.set(&instr, 3, value);
+                self.pc += instr.size();
+            }
+            Opcode::AdjustBase => {
+                self.relative_base += self.get(&instr, 1);
+                self.pc += instr.size();
+            }
+            Opcode::Halt => {
+                self.status = Status::Halted;
+                return self.status;
+            }
+        }
+
+        self.status = Status::Running;
+        return self.status;
+    }
+
+    // Runs until the machine halts or needs input it doesn't have. Any
+    // outputs produced along the way are queued up for take_output(s).
+    pub fn run_until_input(&mut self) -> Status {
+        loop {
+            let status = self.step();
+            if status != Status::Running {
+                return status;
+            }
+        }
+    }
+
+    // Like run_until_input, but returns None if the machine is never going to
+    // stop: it's got back into exactly the same state it was in earlier
+    // without reading any input (outputs aside), or it's still going after
+    // max_steps. States are saved at doubling intervals, so a loop is caught
+    // within a couple of times its length.
+    pub fn run_until_input_or_loop(&mut self, max_steps: usize) -> Option<Status> {
+        let mut saved = (
+            self.memory.clone(),
+            self.pc,
+            self.relative_base,
+            self.inputs.len(),
+        );
+        let mut interval = 1;
+        let mut since_saved = 0;
+        for _ in 0..max_steps {
+            let status = self.step();
+            if status != Status::Running {
+                return Some(status);
+            }
+            let (memory, pc, relative_base, num_inputs) = &saved;
+            if self.pc == *pc
+                && self.relative_base == *relative_base
+                && self.inputs.len() == *num_inputs
+                && self.memory == *memory
+            {
+                return None;
+            }
+            since_saved += 1;
+            if since_saved == interval {
+                saved = (
+                    self.memory.clone(),
+                    self.pc,
+                    self.relative_base,
+                    self.inputs.len(),
+                );
+                interval *= 2;
+                since_saved = 0;
+            }
+        }
+        return None;
+    }
+
+    // Returns the next output, running the machine if there isn't one queued
+    // already. Returns None if the machine halts or blocks on input first.
+    pub fn run_until_output(&mut self) -> Option<i64> {
+        loop {
+            if let Some(value) = self.outputs.pop_front() {
+                return Some(value);
+            }
+            if self.step() != Status::Running {
+                return None;
+            }
+        }
+    }
+
+    // Runs to completion with callbacks for I/O, the same way the old
+    // per-day run_program functions worked. If input returns None the
+    // machine stops and WaitingForInput is returned, so it can be resumed
+    // later.
+    pub fn run<I, O>(&mut self, mut input: I, mut output: O) -> Status
+    where
+        I: FnMut() -> Option<i64>,
+        O: FnMut(i64),
+    {
+        loop {
+            let status = self.step();
+            while let Some(value) = self.outputs.pop_front() {
+                output(value);
+            }
+            match status {
+                Status::Running => {}
+                Status::WaitingForInput => match input() {
+                    Some(value) => self.push_input(value),
+                    None => return status,
+                },
+                Status::Halted => return status,
+            }
+        }
+    }
+
+    pub(crate) fn param_addr(&self, instr: &Instruction, num: usize) -> i64 {
+        let i = self.pc + num as i64;
+        return match instr.modes[num - 1] {
+            Mode::Position => self.read(i),
+            Mode::Immediate => i,
+            Mode::Relative => self.read(i) + self.relative_base,
+        };
+    }
+
+    pub(crate) fn get(&self, instr: &Instruction, num: usize) -> i64 {
+        return self.read(self.param_addr(instr, num));
+    }
+
+    fn set(&mut self, instr: &Instruction, num: usize, value: i64) {
+        let addr = self.param_addr(instr, num);
+        self.write(addr, value);
+    }
+}
+
+// A saved copy of a machine's full state, including queued input and output
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Snapshot {
+    machine: Machine,
+}
+
+impl Snapshot {
+    pub fn to_machine(&self) -> Machine {
+        return self.machine.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_with_inputs(program: &[i64], inputs: Vec<i64>) -> (Machine, Vec<i64>) {
+        let mut machine = Machine::new(program);
+        machine.push_inputs(inputs);
+        machine.run_until_input();
+        let outputs = machine.take_outputs();
+        return (machine, outputs);
+    }
+
+    #[test]
+    fn test_add_mul() {
+        let (machine, _) = run_with_inputs(&[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50], vec![]);
+        assert!(machine.is_halted());
+        assert_eq!(machine.read(0), 3500);
+        // past the end of the program reads as 0
+        assert_eq!(machine.read(5000), 0);
+    }
+
+    #[test]
+    fn test_compare_and_jump() {
+        let program = [3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        assert_eq!(run_with_inputs(&program, vec![8]).1, vec![1]);
+        assert_eq!(run_with_inputs(&program, vec![7]).1, vec![0]);
+        let program = [3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+        assert_eq!(run_with_inputs(&program, vec![0]).1, vec![0]);
+        assert_eq!(run_with_inputs(&program, vec![5]).1, vec![1]);
+    }
+
+    #[test]
+    fn test_relative_base() {
+        let quine = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        assert_eq!(run_with_inputs(&quine, vec![]).1, quine.to_vec());
+        let big = [104, 1125899906842624, 99];
+        assert_eq!(run_with_inputs(&big, vec![]).1, vec![1125899906842624]);
+    }
+
+    #[test]
+    fn test_waits_for_input() {
+        let mut machine = Machine::new(&[3, 0, 4, 0, 99]);
+        assert_eq!(machine.run_until_input(), Status::WaitingForInput);
+        assert_eq!(machine.pc(), 0);
+        assert_eq!(machine.run_until_output(), None);
+        machine.push_input(7);
+        assert_eq!(machine.run_until_output(), Some(7));
+        assert_eq!(machine.run_until_input(), Status::Halted);
+    }
+
+    #[test]
+    fn test_run_with_callbacks() {
+        let mut outputs = Vec::new();
+        let mut inputs = vec![4, 5];
+        let status =
+            Machine::new(&[3, 10, 4, 10, 1105, 1, 0]).run(|| inputs.pop(), |v| outputs.push(v));
+        assert_eq!(status, Status::WaitingForInput);
+        assert_eq!(outputs, vec![5, 4]);
+    }
+
+    #[test]
+    fn test_detects_loop() {
+        let mut machine = Machine::new(&[1105, 1, 0]);
+        assert_eq!(machine.run_until_input_or_loop(1000), None);
+        let mut machine = Machine::new(&[1101, 1, 1, 5, 99]);
+        assert_eq!(machine.run_until_input_or_loop(1000), Some(Status::Halted));
+    }
+}
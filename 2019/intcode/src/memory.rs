@@ -0,0 +1,161 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+const PAGE_BITS: usize = 10;
+const PAGE_SIZE: usize = 1 << PAGE_BITS;
+
+type Page = [i64; PAGE_SIZE];
+
+// Dense memory split into fixed-size pages that are shared between clones
+// until one of them writes to a page, so forking a machine only costs a
+// vec of pointers. Pages that have never been written all share a single
+// zero page.
+#[derive(Clone)]
+pub struct Memory {
+    pages: Vec<Arc<Page>>,
+    zero_page: Arc<Page>,
+    len: usize,
+}
+
+impl Memory {
+    pub fn new(values: &[i64]) -> Memory {
+        let zero_page = Arc::new([0; PAGE_SIZE]);
+        let pages = values
+            .chunks(PAGE_SIZE)
+            .map(|chunk| {
+                let mut page = [0; PAGE_SIZE];
+                page[..chunk.len()].copy_from_slice(chunk);
+                return Arc::new(page);
+            })
+            .collect();
+        return Memory { pages, zero_page, len: values.len() };
+    }
+
+    // One past the highest address that's been initialized or written to
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    pub fn read(&self, addr: usize) -> i64 {
+        return match self.pages.get(addr >> PAGE_BITS) {
+            Some(page) => page[addr & (PAGE_SIZE - 1)],
+            None => 0,
+        };
+    }
+
+    pub fn write(&mut self, addr: usize, value: i64) {
+        let page_idx = addr >> PAGE_BITS;
+        while self.pages.len() <= page_idx {
+            self.pages.push(self.zero_page.clone());
+        }
+        Arc::make_mut(&mut self.pages[page_idx])[addr & (PAGE_SIZE - 1)] = value;
+        if addr >= self.len {
+            self.len = addr + 1;
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<i64> {
+        return (0..self.len).map(|addr| self.read(addr)).collect();
+    }
+
+    // Number of pages this memory doesn't share with anything else
+    pub fn private_pages(&self) -> usize {
+        return self
+            .pages
+            .iter()
+            .filter(|page| Arc::strong_count(page) == 1)
+            .count();
+    }
+}
+
+impl PartialEq for Memory {
+    fn eq(&self, other: &Memory) -> bool {
+        if self.len != other.len {
+            return false;
+        }
+        return self
+            .pages
+            .iter()
+            .zip(other.pages.iter())
+            .all(|(a, b)| Arc::ptr_eq(a, b) || a[..] == b[..]);
+    }
+}
+
+impl Eq for Memory {}
+
+impl Hash for Memory {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        for page in &self.pages {
+            page[..].hash(state);
+        }
+    }
+}
+
+impl fmt::Debug for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return f.debug_list().entries(self.to_vec()).finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::Machine;
+
+    #[test]
+    fn test_read_write() {
+        let mut memory = Memory::new(&[1, 2, 3]);
+        assert_eq!(
+            (memory.len(), memory.read(2), memory.read(1 << 20)),
+            (3, 3, 0)
+        );
+        memory.write(5000, 9);
+        assert_eq!(memory.len(), 5001);
+        assert_eq!(memory.read(5000), 9);
+        assert_eq!(memory.read(4999), 0);
+        assert_eq!(memory.to_vec()[..4], [1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn test_pages_shared_until_written() {
+        let mut memory = Memory::new(&vec![7; PAGE_SIZE * 3]);
+        let copy = memory.clone();
+        assert_eq!(memory.private_pages(), 0);
+        memory.write(PAGE_SIZE + 1, 8);
+        assert_eq!(memory.private_pages(), 1);
+        assert_eq!(copy.read(PAGE_SIZE + 1), 7);
+        assert_ne!(memory, copy);
+        memory.write(PAGE_SIZE + 1, 7);
+        // same contents, even though the page isn't shared any more
+        assert_eq!(memory, copy);
+    }
+
+    #[test]
+    fn test_fork_is_independent() {
+        // adds each input to [20] and outputs the total
+        let program = [3, 21, 1, 20, 21, 20, 4, 20, 1105, 1, 0];
+        let mut machine = Machine::new(&program);
+        machine.push_input(5);
+        assert_eq!(machine.run_until_output(), Some(5));
+
+        let mut fork = machine.fork();
+        let snapshot = machine.snapshot();
+        fork.push_input(10);
+        assert_eq!(fork.run_until_output(), Some(15));
+        assert_eq!(fork.read(20), 15);
+        assert_eq!(machine.read(20), 5);
+
+        machine.push_input(1);
+        assert_eq!(machine.run_until_output(), Some(6));
+        machine.restore(&snapshot);
+        machine.push_input(2);
+        assert_eq!(machine.run_until_output(), Some(7));
+        assert_eq!(snapshot.to_machine().read(20), 5);
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::thread;
+
+use crate::machine::{Machine, Status};
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub struct Packet {
+    pub dest: i64,
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub enum Response {
+    Continue,
+    Send(Packet),
+    Stop(i64),
+}
+
+pub trait NetworkHooks {
+    // Called for every packet sent between machines, before it's queued
+    fn on_packet(&mut self, _packet: &Packet) {}
+    // Called for packets addressed outside the network (like a NAT)
+    fn on_external(&mut self, packet: Packet) -> Response {
+        panic!("No machine at address {}: {:?}", packet.dest, packet);
+    }
+    // Called when every queue is empty and every machine is stuck: told
+    // there's no input, it came back asking again in exactly the same state
+    // without sending anything. Nothing can change after that unless a packet
+    // is sent, so continuing without sending one is an error.
+    fn on_idle(&mut self) -> Response {
+        panic!("Network went idle");
+    }
+}
+
+// The routing state shared by all the machines on the network
+struct Switch {
+    queues: Vec<VecDeque<Packet>>,
+    // outputs that don't make up a full packet yet, per machine
+    partial: Vec<Vec<i64>>,
+    // whether the last input each machine got was "nothing queued"
+    starved: Vec<bool>,
+    // halted, or stuck polling with nothing queued
+    idle: Vec<bool>,
+    result: Option<i64>,
+}
+
+impl Switch {
+    fn new(size: usize) -> Switch {
+        return Switch {
+            queues: vec![VecDeque::new(); size],
+            partial: vec![Vec::new(); size],
+            starved: vec![false; size],
+            idle: vec![false; size],
+            result: None,
+        };
+    }
+
+    fn respond(&mut self, response: Response, hooks: &mut dyn NetworkHooks) {
+        match response {
+            Response::Continue => {}
+            Response::Send(packet) => self.deliver(packet, hooks),
+            Response::Stop(value) => self.result = Some(value),
+        }
+    }
+
+    fn deliver(&mut self, packet: Packet, hooks: &mut dyn NetworkHooks) {
+        if packet.dest >= 0 && (packet.dest as usize) < self.queues.len() {
+            hooks.on_packet(&packet);
+            self.queues[packet.dest as usize].push_back(packet);
+            self.idle[packet.dest as usize] = false;
+        } else {
+            let response = hooks.on_external(packet);
+            self.respond(response, hooks);
+        }
+    }
+
+    // Routes whatever the machine sent during its last turn, and returns what
+    // it should be given for its next input
+    fn take_turn(
+        &mut self,
+        idx: usize,
+        status: Status,
+        outputs: Vec<i64>,
+        stuck: bool,
+        hooks: &mut dyn NetworkHooks,
+    ) -> Vec<i64> {
+        self.idle[idx] = status == Status::Halted || stuck;
+        self.partial[idx].extend(outputs);
+        while self.partial[idx].len() >= 3 {
+            let vals: Vec<i64> = self.partial[idx].drain(..3).collect();
+            self.deliver(Packet { dest: vals[0], x: vals[1], y: vals[2] }, hooks);
+        }
+        if status == Status::Halted {
+            return vec![];
+        }
+        match self.queues[idx].pop_front() {
+            Some(packet) => {
+                self.starved[idx] = false;
+                self.idle[idx] = false;
+                return vec![packet.x, packet.y];
+            }
+            None => {
+                self.starved[idx] = true;
+                return vec![-1];
+            }
+        }
+    }
+
+    fn check_idle(&mut self, hooks: &mut dyn NetworkHooks) {
+        if self.result.is_some() || !self.idle.iter().all(|i| *i) {
+            return;
+        }
+        if self.queues.iter().any(|q| !q.is_empty()) {
+            return;
+        }
+        match hooks.on_idle() {
+            Response::Continue => panic!("Network is idle and nothing was sent"),
+            response => self.respond(response, hooks),
+        }
+    }
+}
+
+// The machine's state just before it's told there's no input, so the next
+// turn can tell whether that changed anything
+fn poll_state(machine: &Machine, input: &[i64]) -> Option<Machine> {
+    if input == [-1] {
+        return Some(machine.fork());
+    }
+    return None;
+}
+
+fn is_stuck(machine: &Machine, outputs: &[i64], polled: &Option<Machine>) -> bool {
+    return outputs.is_empty() && polled.as_ref() == Some(machine);
+}
+
+// A set of machines running the same program that talk to each other with
+// (address, x, y) packets. Each machine is given its address as its first
+// input, and -1 whenever it asks for input and has nothing queued.
+pub struct Network {
+    pub machines: Vec<Machine>,
+}
+
+impl Network {
+    pub fn new(program: &[i64], size: usize) -> Network {
+        let machines = (0..size)
+            .map(|addr| {
+                let mut machine = Machine::new(program);
+                machine.push_input(addr as i64);
+                return machine;
+            })
+            .collect();
+        return Network { machines };
+    }
+
+    // Runs the machines one at a time in order, each until it next asks for
+    // input, so results are the same every time.
+    pub fn run(&mut self, hooks: &mut dyn NetworkHooks) -> i64 {
+        let mut switch = Switch::new(self.machines.len());
+        let mut polled: Vec<Option<Machine>> = vec![None; self.machines.len()];
+        loop {
+            for (idx, machine) in self.machines.iter_mut().enumerate() {
+                let status = machine.run_until_input();
+                let outputs = machine.take_outputs();
+                let stuck = is_stuck(machine, &outputs, &polled[idx]);
+                let input = switch.take_turn(idx, status, outputs, stuck, hooks);
+                polled[idx] = poll_state(machine, &input);
+                machine.push_inputs(input);
+                if let Some(result) = switch.result {
+                    return result;
+                }
+            }
+            switch.check_idle(hooks);
+            if let Some(result) = switch.result {
+                return result;
+            }
+        }
+    }
+
+    // Runs each machine on its own thread. The order packets arrive in isn't
+    // fixed, but idle detection works the same way as in run.
+    pub fn run_parallel<H>(&mut self, hooks: &mut H) -> i64
+    where
+        H: NetworkHooks + Send,
+    {
+        let shared = Mutex::new((Switch::new(self.machines.len()), hooks));
+        thread::scope(|scope| {
+            for (idx, machine) in self.machines.iter_mut().enumerate() {
+                let shared = &shared;
+                let mut polled = None;
+                scope.spawn(move || loop {
+                    let status = machine.run_until_input();
+                    let outputs = machine.take_outputs();
+                    let stuck = is_stuck(machine, &outputs, &polled);
+                    let starved;
+                    {
+                        let mut guard = shared.lock().unwrap();
+                        let (switch, hooks) = &mut *guard;
+                        if switch.result.is_some() {
+                            return;
+                        }
+                        let input = switch.take_turn(idx, status, outputs, stuck, *hooks);
+                        switch.check_idle(*hooks);
+                        if status == Status::Halted || switch.result.is_some() {
+                            return;
+                        }
+                        starved = switch.starved[idx];
+                        polled = poll_state(machine, &input);
+                        machine.push_inputs(input);
+                    }
+                    if starved {
+                        thread::yield_now();
+                    }
+                });
+            }
+        });
+        let (switch, _) = shared.into_inner().unwrap();
+        return switch.result.unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // machine 0 sends (3, 4) to machine 1, and every machine passes whatever
+    // it gets on to 255
+    const FORWARD: [i64; 31] = [
+        3, 100, 1005, 100, 11, 104, 1, 104, 3, 104, 4, 3, 101, 1008, 101, -1, 103, 1005, 103, 11,
+        3, 102, 104, 255, 4, 101, 4, 102, 1105, 1, 11,
+    ];
+
+    // just keeps asking for input
+    const POLL: [i64; 7] = [3, 100, 3, 101, 1105, 1, 2];
+
+    #[derive(Default)]
+    struct Recorder {
+        internal: Vec<Packet>,
+        idle: usize,
+    }
+
+    impl NetworkHooks for Recorder {
+        fn on_packet(&mut self, packet: &Packet) {
+            self.internal.push(*packet);
+        }
+
+        fn on_external(&mut self, packet: Packet) -> Response {
+            assert_eq!(packet.dest, 255);
+            return Response::Stop(packet.x * 10 + packet.y);
+        }
+
+        fn on_idle(&mut self) -> Response {
+            self.idle += 1;
+            return Response::Stop(-7);
+        }
+    }
+
+    #[test]
+    fn test_routes_packets() {
+        let mut hooks = Recorder::default();
+        assert_eq!(Network::new(&FORWARD, 3).run(&mut hooks), 34);
+        assert_eq!(hooks.internal, vec![Packet { dest: 1, x: 3, y: 4 }]);
+        assert_eq!(hooks.idle, 0);
+
+        let mut hooks = Recorder::default();
+        assert_eq!(Network::new(&FORWARD, 3).run_parallel(&mut hooks), 34);
+    }
+
+    #[test]
+    fn test_goes_idle() {
+        let mut hooks = Recorder::default();
+        assert_eq!(Network::new(&POLL, 4).run(&mut hooks), -7);
+        assert_eq!(hooks.idle, 1);
+        assert!(hooks.internal.is_empty());
+
+        let mut hooks = Recorder::default();
+        assert_eq!(Network::new(&POLL, 4).run_parallel(&mut hooks), -7);
+        assert_eq!(hooks.idle, 1);
+    }
+}
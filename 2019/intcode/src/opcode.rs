@@ -0,0 +1,121 @@
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub enum Opcode {
+    Add,
+    Mul,
+    In,
+    Out,
+    JumpTrue,
+    JumpFalse,
+    LessThan,
+    Equals,
+    AdjustBase,
+    Halt,
+}
+
+impl Opcode {
+    pub fn from_value(value: i64) -> Option<Opcode> {
+        return match value {
+            1 => Some(Opcode::Add),
+            2 => Some(Opcode::Mul),
+            3 => Some(Opcode::In),
+            4 => Some(Opcode::Out),
+            5 => Some(Opcode::JumpTrue),
+            6 => Some(Opcode::JumpFalse),
+            7 => Some(Opcode::LessThan),
+            8 => Some(Opcode::Equals),
+            9 => Some(Opcode::AdjustBase),
+            99 => Some(Opcode::Halt),
+            _ => None,
+        };
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        return match self {
+            Opcode::Add => "ADD",
+            Opcode::Mul => "MUL",
+            Opcode::In => "IN",
+            Opcode::Out => "OUT",
+            Opcode::JumpTrue => "JT",
+            Opcode::JumpFalse => "JF",
+            Opcode::LessThan => "LT",
+            Opcode::Equals => "EQ",
+            Opcode::AdjustBase => "ARB",
+            Opcode::Halt => "HLT",
+        };
+    }
+
+    // Which param (1-based) is the address this opcode stores into, if any
+    pub fn write_param(&self) -> Option<usize> {
+        return match self {
+            Opcode::Add | Opcode::Mul | Opcode::LessThan | Opcode::Equals => Some(3),
+            Opcode::In => Some(1),
+            _ => None,
+        };
+    }
+
+    pub fn num_params(&self) -> usize {
+        return match self {
+            Opcode::Add | Opcode::Mul | Opcode::LessThan | Opcode::Equals => 3,
+            Opcode::JumpTrue | Opcode::JumpFalse => 2,
+            Opcode::In | Opcode::Out | Opcode::AdjustBase => 1,
+            Opcode::Halt => 0,
+        };
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub enum Mode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl Mode {
+    pub fn from_value(value: i64) -> Option<Mode> {
+        return match value {
+            0 => Some(Mode::Position),
+            1 => Some(Mode::Immediate),
+            2 => Some(Mode::Relative),
+            _ => None,
+        };
+    }
+
+    // How a param with this mode and raw value shows up in a listing
+    pub fn format_param(&self, value: i64) -> String {
+        return match self {
+            Mode::Position => format!("[{}]", value),
+            Mode::Immediate => value.to_string(),
+            Mode::Relative if value < 0 => format!("[rb-{}]", -value),
+            Mode::Relative => format!("[rb+{}]", value),
+        };
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    // only the first opcode.num_params() entries are meaningful
+    pub modes: [Mode; 3],
+}
+
+impl Instruction {
+    pub fn size(&self) -> i64 {
+        return 1 + self.opcode.num_params() as i64;
+    }
+}
+
+// Returns None if the value isn't a valid opcode, or if it has a bad mode
+// digit for one of its params.
+pub fn decode(value: i64) -> Option<Instruction> {
+    if value < 0 {
+        return None;
+    }
+    let opcode = Opcode::from_value(value % 100)?;
+    let mut modes = [Mode::Position; 3];
+    let mut flags = value / 100;
+    for mode in modes.iter_mut().take(opcode.num_params()) {
+        *mode = Mode::from_value(flags % 10)?;
+        flags /= 10;
+    }
+    return Some(Instruction { opcode, modes });
+}
@@ -0,0 +1,26 @@
+use failure::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+pub fn parse_program(line: &str) -> Result<Vec<i64>, Error> {
+    return line
+        .split(',')
+        .map(|val| Ok(val.trim().parse::<i64>()?))
+        .collect();
+}
+
+// Programs are usually one long line, but this will happily concatenate
+// several if they've been wrapped.
+pub fn read_program(file: &str) -> Result<Vec<i64>, Error> {
+    let f = File::open(file)?;
+    let br = BufReader::new(f);
+    let mut program = Vec::new();
+    for line in br.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        program.extend(parse_program(&line)?);
+    }
+    return Ok(program);
+}
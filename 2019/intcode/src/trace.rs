@@ -0,0 +1,298 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+use crate::machine::{Machine, Status};
+use crate::opcode::Instruction;
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TraceRecord {
+    pub pc: i64,
+    pub relative_base: i64,
+    pub instr: Instruction,
+    // the raw param values as they appear in the program
+    pub raw_params: Vec<i64>,
+    // what each param resolved to: the value read, or for the param being
+    // written to, the address
+    pub params: Vec<i64>,
+    // (address, old value, new value)
+    pub write: Option<(i64, i64, i64)>,
+}
+
+impl fmt::Display for TraceRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let args: Vec<String> = self
+            .raw_params
+            .iter()
+            .enumerate()
+            .map(|(idx, param)| self.instr.modes[idx].format_param(*param))
+            .collect();
+        let vals: Vec<String> = self.params.iter().map(|v| v.to_string()).collect();
+        write!(
+            f,
+            "{:>6}  {:<4} {:<30}",
+            self.pc,
+            self.instr.opcode.mnemonic(),
+            args.join(", ")
+        )?;
+        if !vals.is_empty() {
+            write!(f, " ; {}", vals.join(", "))?;
+        }
+        if let Some((addr, old, new)) = self.write {
+            write!(f, " ; [{}] {} -> {}", addr, old, new)?;
+        }
+        return Ok(());
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub enum StopReason {
+    Halted,
+    WaitingForInput,
+    // about to execute the instruction at this pc
+    Breakpoint(i64),
+    // (address, old value, new value) of the write that just happened
+    Watchpoint(i64, i64, i64),
+    StepLimit,
+}
+
+// Steps a machine one instruction at a time, keeping a record of what each
+// instruction did. Stopping at a breakpoint leaves the machine with pc at
+// the breakpoint; calling run again resumes past it.
+#[derive(Default)]
+pub struct Tracer {
+    pub breakpoints: HashSet<i64>,
+    pub watchpoints: HashSet<i64>,
+    // stop once this many instructions have been executed in total
+    pub step_limit: Option<usize>,
+    // how many of the most recent records to keep in history
+    pub history_size: usize,
+    pub history: VecDeque<TraceRecord>,
+    pub steps: usize,
+    pub verbose: bool,
+    resume_from: Option<i64>,
+}
+
+impl Tracer {
+    pub fn new() -> Tracer {
+        return Tracer::default();
+    }
+
+    // Executes a single instruction. Returns a StopReason if that (or the
+    // state of the machine before it) is something the caller asked to
+    // stop on.
+    pub fn step(&mut self, machine: &mut Machine) -> Option<StopReason> {
+        if machine.is_halted() {
+            return Some(StopReason::Halted);
+        }
+
+        let pc = machine.pc();
+        if self.breakpoints.contains(&pc) && self.resume_from != Some(pc) {
+            self.resume_from = Some(pc);
+            return Some(StopReason::Breakpoint(pc));
+        }
+        if let Some(limit) = self.step_limit {
+            if self.steps >= limit {
+                return Some(StopReason::StepLimit);
+            }
+        }
+
+        let instr = match machine.current_instruction() {
+            Some(instr) => instr,
+            None => panic!("Unknown opcode at {}: {}", pc, machine.read(pc)),
+        };
+        let write_param = instr.opcode.write_param();
+        let mut raw_params = Vec::new();
+        let mut params = Vec::new();
+        for num in 1..=instr.opcode.num_params() {
+            raw_params.push(machine.read(pc + num as i64));
+            if write_param == Some(num) {
+                params.push(machine.param_addr(&instr, num));
+            } else {
+                params.push(machine.get(&instr, num));
+            }
+        }
+        let write_addr = write_param.map(|num| params[num - 1]);
+        let old_value = write_addr.map(|addr| machine.read(addr));
+        let relative_base = machine.relative_base();
+
+        let status = machine.step();
+        if status == Status::WaitingForInput {
+            return Some(StopReason::WaitingForInput);
+        }
+        self.steps += 1;
+        self.resume_from = None;
+
+        let write = match (write_addr, old_value) {
+            (Some(addr), Some(old)) => Some((addr, old, machine.read(addr))),
+            _ => None,
+        };
+        let record = TraceRecord { pc, relative_base, instr, raw_params, params, write };
+        if self.verbose {
+            println!("{}", record);
+        }
+        if self.history_size > 0 {
+            if self.history.len() >= self.history_size {
+                self.history.pop_front();
+            }
+            self.history.push_back(record);
+        }
+
+        if status == Status::Halted {
+            return Some(StopReason::Halted);
+        }
+        if let Some((addr, old, new)) = write {
+            if self.watchpoints.contains(&addr) {
+                return Some(StopReason::Watchpoint(addr, old, new));
+            }
+        }
+        return None;
+    }
+
+    pub fn run(&mut self, machine: &mut Machine) -> StopReason {
+        loop {
+            if let Some(reason) = self.step(machine) {
+                return reason;
+            }
+        }
+    }
+
+    // Like Machine::run, with callbacks for I/O, but also stops for the
+    // tracer's own reasons. If input returns None it stops with
+    // WaitingForInput, and the machine can be resumed later.
+    pub fn run_with_io<I, O>(
+        &mut self,
+        machine: &mut Machine,
+        mut input: I,
+        mut output: O,
+    ) -> StopReason
+    where
+        I: FnMut() -> Option<i64>,
+        O: FnMut(i64),
+    {
+        loop {
+            let reason = self.step(machine);
+            while let Some(value) = machine.take_output() {
+                output(value);
+            }
+            match reason {
+                None => {}
+                Some(StopReason::WaitingForInput) => match input() {
+                    Some(value) => machine.push_input(value),
+                    None => return StopReason::WaitingForInput,
+                },
+                Some(reason) => return reason,
+            }
+        }
+    }
+
+    // Like Machine::run_until_output, but also stops for the tracer's own
+    // reasons. Returns Ok with the output, or Err with why it stopped first.
+    pub fn run_until_output(&mut self, machine: &mut Machine) -> Result<i64, StopReason> {
+        loop {
+            if let Some(value) = machine.take_output() {
+                return Ok(value);
+            }
+            if let Some(reason) = self.step(machine) {
+                return Err(reason);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // [20] = 2 + 3, [21] = [20] * 2, then echoes one input
+    const PROGRAM: [i64; 13] = [1101, 2, 3, 20, 1002, 20, 2, 21, 3, 22, 4, 22, 99];
+
+    #[test]
+    fn test_runs_until_input() {
+        let mut machine = Machine::new(&PROGRAM);
+        let mut tracer = Tracer::new();
+        tracer.history_size = 5;
+        assert_eq!(tracer.run(&mut machine), StopReason::WaitingForInput);
+        assert_eq!(tracer.steps, 2);
+        assert_eq!(machine.pc(), 8);
+
+        let first = &tracer.history[0];
+        assert_eq!((first.pc, first.relative_base), (0, 0));
+        assert_eq!(first.raw_params, vec![2, 3, 20]);
+        assert_eq!(first.params, vec![2, 3, 20]);
+        assert_eq!(first.write, Some((20, 0, 5)));
+        // the first param is in position mode, so it's resolved to what's there
+        assert_eq!(tracer.history[1].params, vec![5, 2, 21]);
+
+        machine.push_input(7);
+        assert_eq!(tracer.run_until_output(&mut machine), Ok(7));
+        assert_eq!(tracer.run(&mut machine), StopReason::Halted);
+        assert_eq!(tracer.steps, 5);
+        assert_eq!(tracer.history.len(), 5);
+        // it stays halted
+        assert_eq!(tracer.step(&mut machine), Some(StopReason::Halted));
+    }
+
+    #[test]
+    fn test_history_keeps_the_latest() {
+        let mut machine = Machine::new(&PROGRAM);
+        let mut tracer = Tracer::new();
+        tracer.history_size = 1;
+        tracer.run(&mut machine);
+        assert_eq!(tracer.history.len(), 1);
+        assert_eq!(tracer.history[0].pc, 4);
+        assert_eq!(tracer.history[0].write, Some((21, 0, 10)));
+
+        let mut tracer = Tracer::new();
+        tracer.run(&mut Machine::new(&PROGRAM));
+        assert!(tracer.history.is_empty());
+    }
+
+    #[test]
+    fn test_breakpoint() {
+        let mut machine = Machine::new(&PROGRAM);
+        let mut tracer = Tracer::new();
+        tracer.breakpoints.insert(4);
+        assert_eq!(tracer.run(&mut machine), StopReason::Breakpoint(4));
+        assert_eq!(machine.pc(), 4);
+        assert_eq!(machine.read(21), 0);
+        // running again goes past it
+        assert_eq!(tracer.run(&mut machine), StopReason::WaitingForInput);
+        assert_eq!(machine.read(21), 10);
+    }
+
+    #[test]
+    fn test_watchpoint() {
+        let mut machine = Machine::new(&PROGRAM);
+        let mut tracer = Tracer::new();
+        tracer.watchpoints.insert(21);
+        assert_eq!(tracer.run(&mut machine), StopReason::Watchpoint(21, 0, 10));
+        assert_eq!(machine.pc(), 8);
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let mut machine = Machine::new(&PROGRAM);
+        let mut tracer = Tracer::new();
+        tracer.step_limit = Some(1);
+        assert_eq!(tracer.run(&mut machine), StopReason::StepLimit);
+        assert_eq!(tracer.steps, 1);
+        assert_eq!(machine.pc(), 4);
+        assert_eq!(
+            tracer.run_until_output(&mut machine),
+            Err(StopReason::StepLimit)
+        );
+    }
+
+    #[test]
+    fn test_run_with_io() {
+        let mut machine = Machine::new(&PROGRAM);
+        let mut tracer = Tracer::new();
+        let mut outputs = Vec::new();
+        let reason = tracer.run_with_io(&mut machine, || None, |val| outputs.push(val));
+        assert_eq!(reason, StopReason::WaitingForInput);
+
+        let reason = tracer.run_with_io(&mut machine, || Some(4), |val| outputs.push(val));
+        assert_eq!(reason, StopReason::Halted);
+        assert_eq!(outputs, vec![4]);
+    }
+}
@@ -0,0 +1,327 @@
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+
+use crate::grid::{Coord, GridCoord};
+use crate::grid3d::Coord3d;
+use crate::solver::SolverState;
+
+// A coord with any number of dimensions, for when the puzzle goes from 3d to 4d halfway
+// through (2020 day17) or the same code should work on both Coord and Coord3d. They
+// convert back and forth with From/Into, and it can be added, subtracted, negated and
+// multiplied by a number like a vector.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug, Hash)]
+pub struct CoordN<const D: usize> {
+    pub vals: [i32; D],
+}
+
+impl<const D: usize> CoordN<D> {
+    pub fn new(vals: [i32; D]) -> Self {
+        return CoordN { vals };
+    }
+
+    pub fn origin() -> Self {
+        return CoordN { vals: [0; D] };
+    }
+
+    // One step along the given axis, in the given direction
+    pub fn unit(axis: usize, sign: i32) -> Self {
+        let mut vals = [0; D];
+        vals[axis] = sign;
+        return CoordN { vals };
+    }
+}
+
+impl<const D: usize> SolverState for CoordN<D> {}
+
+impl<const D: usize> GridCoord for CoordN<D> {
+    fn extend_bounds(min: &mut Self, max: &mut Self, coord: &Self) {
+        for axis in 0..D {
+            min[axis] = min[axis].min(coord[axis]);
+            max[axis] = max[axis].max(coord[axis]);
+        }
+    }
+
+    fn in_bounds(&self, min: &Self, max: &Self) -> bool {
+        return in_bounds_n(self, min, max);
+    }
+}
+
+impl<const D: usize> Index<usize> for CoordN<D> {
+    type Output = i32;
+
+    fn index(&self, axis: usize) -> &i32 {
+        return &self.vals[axis];
+    }
+}
+
+impl<const D: usize> IndexMut<usize> for CoordN<D> {
+    fn index_mut(&mut self, axis: usize) -> &mut i32 {
+        return &mut self.vals[axis];
+    }
+}
+
+impl<const D: usize> Add for CoordN<D> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        return CoordN { vals: std::array::from_fn(|i| self.vals[i] + other.vals[i]) };
+    }
+}
+
+impl<const D: usize> Sub for CoordN<D> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        return CoordN { vals: std::array::from_fn(|i| self.vals[i] - other.vals[i]) };
+    }
+}
+
+impl<const D: usize> Neg for CoordN<D> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        return CoordN { vals: self.vals.map(|v| -v) };
+    }
+}
+
+impl<const D: usize> Mul<i32> for CoordN<D> {
+    type Output = Self;
+
+    fn mul(self, factor: i32) -> Self {
+        return CoordN { vals: self.vals.map(|v| v * factor) };
+    }
+}
+
+impl<const D: usize> AddAssign for CoordN<D> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const D: usize> SubAssign for CoordN<D> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl From<Coord> for CoordN<2> {
+    fn from(coord: Coord) -> Self {
+        return CoordN { vals: [coord.x, coord.y] };
+    }
+}
+
+impl From<CoordN<2>> for Coord {
+    fn from(coord: CoordN<2>) -> Self {
+        return Coord { x: coord.vals[0], y: coord.vals[1] };
+    }
+}
+
+impl From<Coord3d> for CoordN<3> {
+    fn from(coord: Coord3d) -> Self {
+        return CoordN { vals: [coord.x, coord.y, coord.z] };
+    }
+}
+
+impl From<CoordN<3>> for Coord3d {
+    fn from(coord: CoordN<3>) -> Self {
+        return Coord3d { x: coord.vals[0], y: coord.vals[1], z: coord.vals[2] };
+    }
+}
+
+pub fn manhattan_n<const D: usize>(start: &CoordN<D>, end: &CoordN<D>) -> i32 {
+    return (0..D).map(|i| (end[i] - start[i]).abs()).sum();
+}
+
+// The biggest difference along any one axis, ie how many steps it takes if diagonal
+// moves are allowed
+pub fn chebyshev_n<const D: usize>(start: &CoordN<D>, end: &CoordN<D>) -> i32 {
+    return (0..D).map(|i| (end[i] - start[i]).abs()).max().unwrap_or(0);
+}
+
+// The 2 * D coords one step away along a single axis, the N-d version of four_neighbors
+pub fn orthogonal_neighbors_n<const D: usize>(coord: &CoordN<D>) -> Vec<CoordN<D>> {
+    let mut ret = Vec::with_capacity(2 * D);
+    for axis in 0..D {
+        ret.push(*coord + CoordN::unit(axis, 1));
+        ret.push(*coord + CoordN::unit(axis, -1));
+    }
+    return ret;
+}
+
+// All 3^D - 1 coords touching this one, including diagonally, the N-d version of
+// eight_neighbors
+pub fn all_neighbors_n<const D: usize>(coord: &CoordN<D>) -> Vec<CoordN<D>> {
+    let one = CoordN::new([1; D]);
+    return coords_in_box_n(&(*coord - one), &(*coord + one))
+        .filter(|ngh| ngh != coord)
+        .collect();
+}
+
+// The smallest min and max that have all the coords between them, or None if there
+// aren't any coords
+pub fn bounding_box_n<'a, const D: usize, I>(coords: I) -> Option<(CoordN<D>, CoordN<D>)>
+where
+    I: IntoIterator<Item = &'a CoordN<D>>,
+{
+    let mut bounds: Option<(CoordN<D>, CoordN<D>)> = None;
+    for coord in coords {
+        bounds = Some(match bounds {
+            Some((min, max)) => (
+                CoordN { vals: std::array::from_fn(|i| min[i].min(coord[i])) },
+                CoordN { vals: std::array::from_fn(|i| max[i].max(coord[i])) },
+            ),
+            None => (*coord, *coord),
+        });
+    }
+    return bounds;
+}
+
+pub fn in_bounds_n<const D: usize>(coord: &CoordN<D>, min: &CoordN<D>, max: &CoordN<D>) -> bool {
+    return (0..D).all(|i| coord[i] >= min[i] && coord[i] <= max[i]);
+}
+
+// Every coord from min to max inclusive, with the last axis changing fastest
+pub fn coords_in_box_n<const D: usize>(
+    min: &CoordN<D>,
+    max: &CoordN<D>,
+) -> impl Iterator<Item = CoordN<D>> {
+    let (min, max) = (*min, *max);
+    let mut next = if in_bounds_n(&min, &min, &max) {
+        Some(min)
+    } else {
+        None
+    };
+    return std::iter::from_fn(move || {
+        let cur = next?;
+        let mut following = cur;
+        next = None;
+        for axis in (0..D).rev() {
+            if following[axis] < max[axis] {
+                following[axis] += 1;
+                next = Some(following);
+                break;
+            }
+            following[axis] = min[axis];
+        }
+        return Some(cur);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::Automaton;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_coords_in_box_n() {
+        let coords = coords_in_box_n(&CoordN::new([0, 5]), &CoordN::new([1, 7]))
+            .map(|coord| coord.vals)
+            .collect::<Vec<_>>();
+        assert_eq!(coords, vec![[0, 5], [0, 6], [0, 7], [1, 5], [1, 6], [1, 7]]);
+
+        let point = CoordN::new([1, 2, 3]);
+        assert_eq!(
+            coords_in_box_n(&point, &point).collect::<Vec<_>>(),
+            vec![point]
+        );
+        assert_eq!(coords_in_box_n(&point, &CoordN::new([1, 1, 3])).count(), 0);
+    }
+
+    #[test]
+    fn test_neighbors_n() {
+        let coord = CoordN::new([4, -2, 0, 7]);
+        let all = all_neighbors_n(&coord);
+        assert_eq!(all.len(), 80);
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), 80);
+        assert!(all.iter().all(|ngh| chebyshev_n(&coord, ngh) == 1));
+
+        let orthogonal = orthogonal_neighbors_n(&coord);
+        assert_eq!(orthogonal.len(), 8);
+        assert!(orthogonal.iter().all(|ngh| manhattan_n(&coord, ngh) == 1));
+        assert_eq!(all_neighbors_n(&CoordN::new([0, 0, 0])).len(), 26);
+    }
+
+    #[test]
+    fn test_coordn_math() {
+        let a = CoordN::new([1, -2, 3]);
+        let b = CoordN::new([4, 0, -1]);
+        assert_eq!(a + b, CoordN::new([5, -2, 2]));
+        assert_eq!(a - b, CoordN::new([-3, -2, 4]));
+        assert_eq!(-a, CoordN::new([-1, 2, -3]));
+        assert_eq!(a * 3, CoordN::new([3, -6, 9]));
+        assert_eq!(manhattan_n(&a, &b), 9);
+        assert_eq!(chebyshev_n(&a, &b), 4);
+
+        let mut c = a;
+        c += CoordN::unit(1, 1);
+        c[0] = 7;
+        assert_eq!(c, CoordN::new([7, -1, 3]));
+        c -= CoordN::origin();
+        assert_eq!(c[2], 3);
+    }
+
+    #[test]
+    fn test_bounding_box_n() {
+        let coords = vec![
+            CoordN::new([1, 5]),
+            CoordN::new([-2, 3]),
+            CoordN::new([0, 8]),
+        ];
+        assert_eq!(
+            bounding_box_n(&coords),
+            Some((CoordN::new([-2, 3]), CoordN::new([1, 8])))
+        );
+        assert_eq!(bounding_box_n::<2, _>(&Vec::new()), None);
+        assert!(in_bounds_n(
+            &CoordN::new([0, 3]),
+            &CoordN::new([-2, 3]),
+            &CoordN::new([1, 8])
+        ));
+        assert!(!in_bounds_n(
+            &CoordN::new([0, 9]),
+            &CoordN::new([-2, 3]),
+            &CoordN::new([1, 8])
+        ));
+    }
+
+    #[test]
+    fn test_coordn_from() {
+        let coord = Coord { x: 3, y: -4 };
+        let n: CoordN<2> = coord.into();
+        assert_eq!(n.vals, [3, -4]);
+        assert_eq!(Coord::from(n), coord);
+
+        let coord = Coord3d { x: 1, y: 2, z: 3 };
+        let n = CoordN::from(coord);
+        assert_eq!(n.vals, [1, 2, 3]);
+        assert_eq!(Coord3d::from(n), coord);
+    }
+
+    // 2020 day17's example, in 3 and 4 dimensions
+    fn conway_cubes<const D: usize>() -> usize {
+        let mut automaton = Automaton::new(false, CoordN::<D>::origin());
+        for (y, line) in [".#.", "..#", "###"].iter().enumerate() {
+            for (x, ch) in line.chars().enumerate() {
+                let mut coord = CoordN::origin();
+                coord[0] = x as i32;
+                coord[1] = y as i32;
+                automaton.set(coord, ch == '#');
+            }
+        }
+        automaton.run(
+            6,
+            &mut |coord| all_neighbors_n(coord),
+            &mut |_, active, around| {
+                let count = around.iter().filter(|ngh| ***ngh).count();
+                return count == 3 || (*active && count == 2);
+            },
+        );
+        return automaton.count(&true);
+    }
+
+    #[test]
+    fn test_conway_cubes() {
+        assert_eq!(conway_cubes::<3>(), 112);
+        assert_eq!(conway_cubes::<4>(), 848);
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+
+use crate::grid::{Coord, Grid};
+
+// A grid with a value at every coord from min to max, stored row by row in a Vec, for
+// inputs where every cell matters (heightmaps and the like) so lookups don't need to
+// hash anything
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct DenseGrid<T> {
+    pub min: Coord,
+    pub max: Coord,
+    cells: Vec<T>,
+}
+
+impl<T> DenseGrid<T> {
+    // max can be one less than min to make a grid with nothing in it, but no less
+    pub fn new(min: Coord, max: Coord, val: T) -> Self
+    where
+        T: Clone,
+    {
+        if max.x < min.x - 1 || max.y < min.y - 1 {
+            panic!("Bad bounds for a grid: {:?} to {:?}", min, max);
+        }
+        let size = ((max.x - min.x + 1) * (max.y - min.y + 1)) as usize;
+        return DenseGrid { min, max, cells: vec![val; size] };
+    }
+
+    // Each row is a y, starting from 0. If there aren't any rows, or they're all empty,
+    // the grid doesn't have any rows or columns.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Self {
+        let width = rows.first().map_or(0, |row| row.len());
+        if let Some(row) = rows.iter().find(|row| row.len() != width) {
+            panic!("Rows are different widths: {} and {}", width, row.len());
+        }
+        let height = if width == 0 { 0 } else { rows.len() };
+        return DenseGrid {
+            min: Coord { x: 0, y: 0 },
+            max: Coord { x: width as i32 - 1, y: height as i32 - 1 },
+            cells: rows.into_iter().flatten().collect(),
+        };
+    }
+
+    // Cells that aren't in the sparse grid get fill
+    pub fn from_grid(grid: &Grid<T>, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        let mut dense = DenseGrid::new(grid.min, grid.max, fill);
+        for (coord, val) in &grid.coords {
+            dense[*coord] = val.clone();
+        }
+        return dense;
+    }
+
+    pub fn to_grid(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        return Grid {
+            coords: self
+                .iter()
+                .map(|(coord, val)| (coord, val.clone()))
+                .collect::<HashMap<Coord, T>>(),
+            min: self.min,
+            max: self.max,
+        };
+    }
+
+    pub fn width(&self) -> usize {
+        return (self.max.x - self.min.x + 1) as usize;
+    }
+
+    pub fn height(&self) -> usize {
+        return (self.max.y - self.min.y + 1) as usize;
+    }
+
+    pub fn in_bounds(&self, coord: &Coord) -> bool {
+        return coord.x >= self.min.x
+            && coord.x <= self.max.x
+            && coord.y >= self.min.y
+            && coord.y <= self.max.y;
+    }
+
+    fn index_of(&self, coord: &Coord) -> Option<usize> {
+        if !self.in_bounds(coord) {
+            return None;
+        }
+        let col = (coord.x - self.min.x) as usize;
+        let row = (coord.y - self.min.y) as usize;
+        return Some(row * self.width() + col);
+    }
+
+    // Only called with the index of a cell, so the grid isn't empty and width isn't 0
+    fn coord_of(&self, idx: usize) -> Coord {
+        return Coord {
+            x: self.min.x + (idx % self.width()) as i32,
+            y: self.min.y + (idx / self.width()) as i32,
+        };
+    }
+
+    // None if the coord is off the grid
+    pub fn get(&self, coord: &Coord) -> Option<&T> {
+        return self.index_of(coord).map(|idx| &self.cells[idx]);
+    }
+
+    pub fn get_mut(&mut self, coord: &Coord) -> Option<&mut T> {
+        return self.index_of(coord).map(|idx| &mut self.cells[idx]);
+    }
+
+    pub fn row(&self, y: i32) -> &[T] {
+        if y < self.min.y || y > self.max.y {
+            panic!("Row {} out of range {}..={}", y, self.min.y, self.max.y);
+        }
+        let start = (y - self.min.y) as usize * self.width();
+        return &self.cells[start..start + self.width()];
+    }
+
+    // Not chunks(width), since that panics on a grid with no columns
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        let width = self.width();
+        return (0..self.height()).map(move |row| &self.cells[row * width..(row + 1) * width]);
+    }
+
+    // Always panics on a grid with no columns, so step_by never gets a width of 0
+    pub fn column(&self, x: i32) -> impl Iterator<Item = &T> {
+        if x < self.min.x || x > self.max.x {
+            panic!("Column {} out of range {}..={}", x, self.min.x, self.max.x);
+        }
+        let start = (x - self.min.x) as usize;
+        return self.cells.iter().skip(start).step_by(self.width());
+    }
+
+    // Row by row, like reading the input
+    pub fn coords(&self) -> impl Iterator<Item = Coord> + '_ {
+        return (0..self.cells.len()).map(|idx| self.coord_of(idx));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Coord, &T)> {
+        return self
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(idx, val)| (self.coord_of(idx), val));
+    }
+}
+
+impl<T> Index<Coord> for DenseGrid<T> {
+    type Output = T;
+
+    fn index(&self, coord: Coord) -> &T {
+        match self.get(&coord) {
+            Some(val) => return val,
+            None => panic!("Coord {:?} is off the grid", coord),
+        }
+    }
+}
+
+impl<T> IndexMut<Coord> for DenseGrid<T> {
+    fn index_mut(&mut self, coord: Coord) -> &mut T {
+        match self.index_of(&coord) {
+            Some(idx) => return &mut self.cells[idx],
+            None => panic!("Coord {:?} is off the grid", coord),
+        }
+    }
+}
+
+pub fn print_dense_grid<F, T>(grid: &DenseGrid<T>, render_one: &mut F) -> ()
+where
+    F: FnMut(&Coord, &T) -> String,
+{
+    for (coord, val) in grid.iter() {
+        print!("{}", render_one(&coord, val));
+        if coord.x == grid.max.x {
+            println!();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> DenseGrid<i32> {
+        return DenseGrid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]);
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let grid = sample();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.row(1), &[4, 5, 6]);
+        assert_eq!(
+            grid.rows().collect::<Vec<&[i32]>>(),
+            vec![&[1, 2, 3], &[4, 5, 6]]
+        );
+        assert_eq!(grid.column(2).copied().collect::<Vec<i32>>(), vec![3, 6]);
+        assert_eq!(grid[Coord { x: 1, y: 1 }], 5);
+        assert_eq!(grid.get(&Coord { x: 3, y: 0 }), None);
+        assert_eq!(
+            grid.coords().take(4).collect::<Vec<Coord>>(),
+            vec![
+                Coord { x: 0, y: 0 },
+                Coord { x: 1, y: 0 },
+                Coord { x: 2, y: 0 },
+                Coord { x: 0, y: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_offset_bounds() {
+        let mut grid = DenseGrid::new(Coord { x: -2, y: 5 }, Coord { x: 0, y: 6 }, '.');
+        grid[Coord { x: -2, y: 6 }] = '#';
+        *grid.get_mut(&Coord { x: 0, y: 5 }).unwrap() = '@';
+        assert_eq!(grid.row(5), &['.', '.', '@']);
+        assert_eq!(grid.row(6), &['#', '.', '.']);
+        assert!(grid.get_mut(&Coord { x: 1, y: 5 }).is_none());
+    }
+
+    #[test]
+    fn test_grid_round_trip() {
+        let mut sparse = Grid::new();
+        sparse.insert(Coord { x: 1, y: 1 }, 'a');
+        sparse.insert(Coord { x: 3, y: 2 }, 'b');
+        let dense = DenseGrid::from_grid(&sparse, '.');
+        assert_eq!((dense.min, dense.max), (sparse.min, sparse.max));
+        assert_eq!(dense.row(1), &['a', '.', '.']);
+        assert_eq!(dense.to_grid().coords.len(), 6);
+        assert_eq!(dense.to_grid().coords[&Coord { x: 3, y: 2 }], 'b');
+    }
+
+    #[test]
+    fn test_empty() {
+        for rows in [vec![], vec![vec![], vec![]]] {
+            let grid: DenseGrid<i32> = DenseGrid::from_rows(rows);
+            assert_eq!((grid.width(), grid.height()), (0, 0));
+            assert_eq!(grid.rows().count(), 0);
+            assert_eq!(grid.iter().count(), 0);
+            assert!(!grid.in_bounds(&Coord { x: 0, y: 0 }));
+        }
+        let grid = DenseGrid::new(Coord { x: 0, y: 0 }, Coord { x: -1, y: 4 }, 0);
+        assert_eq!(grid.rows().collect::<Vec<&[i32]>>(), vec![&[] as &[i32]; 5]);
+        assert_eq!(grid.coords().count(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_backwards_bounds() {
+        DenseGrid::new(Coord { x: 0, y: 0 }, Coord { x: -2, y: 3 }, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_column_of_empty() {
+        let _ = DenseGrid::<i32>::from_rows(vec![]).column(0);
+    }
+}
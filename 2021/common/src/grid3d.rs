@@ -1,3 +1,5 @@
+use crate::grid::GridCoord;
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug, Hash)]
 pub struct Coord3d {
     pub x: i32,
@@ -5,6 +7,26 @@ pub struct Coord3d {
     pub z: i32,
 }
 
+impl GridCoord for Coord3d {
+    fn extend_bounds(min: &mut Coord3d, max: &mut Coord3d, coord: &Coord3d) {
+        min.x = min.x.min(coord.x);
+        min.y = min.y.min(coord.y);
+        min.z = min.z.min(coord.z);
+        max.x = max.x.max(coord.x);
+        max.y = max.y.max(coord.y);
+        max.z = max.z.max(coord.z);
+    }
+
+    fn in_bounds(&self, min: &Coord3d, max: &Coord3d) -> bool {
+        return self.x >= min.x
+            && self.x <= max.x
+            && self.y >= min.y
+            && self.y <= max.y
+            && self.z >= min.z
+            && self.z <= max.z;
+    }
+}
+
 #[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
 pub struct Direction3d {
     pub dx: i32,
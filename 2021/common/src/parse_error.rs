@@ -0,0 +1,88 @@
+use std::error::Error;
+use std::fmt;
+
+// What went wrong parsing the input, and where. Lines and columns start at 1, and 0 means
+// we don't know (yet): the helpers in framework fill in whatever the caller left out, so a
+// parse_line can just return ParseError::new("Bad op") and still get the line number.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    // the whole line the problem is on
+    pub text: String,
+    // which part of the input it's in, if it has several (see framework::Sections)
+    pub section: String,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(message: &str) -> ParseError {
+        return ParseError {
+            line: 0,
+            column: 0,
+            text: String::new(),
+            section: String::new(),
+            message: message.to_string(),
+        };
+    }
+
+    // Fills in the column if it isn't known yet
+    pub fn at_column(mut self, column: usize) -> ParseError {
+        if self.column == 0 {
+            self.column = column;
+        }
+        return self;
+    }
+
+    // Fills in the line (and its text) if they aren't known yet
+    pub fn at_line(mut self, line: usize, text: &str) -> ParseError {
+        if self.line == 0 {
+            self.line = line;
+        }
+        if self.text.is_empty() {
+            self.text = text.to_string();
+        }
+        return self;
+    }
+
+    // Fills in the section if it isn't known yet
+    pub fn in_section(mut self, section: &str) -> ParseError {
+        if self.section.is_empty() {
+            self.section = section.to_string();
+        }
+        return self;
+    }
+}
+
+// eg
+//   in section 2 (moves), line 4, column 9: Can't parse "x"
+//     forward x
+//             ^
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut place = Vec::new();
+        if !self.section.is_empty() {
+            place.push(format!("in {}", self.section));
+        }
+        if self.line > 0 {
+            place.push(format!("line {}", self.line));
+        }
+        if self.column > 0 {
+            place.push(format!("column {}", self.column));
+        }
+        if place.is_empty() {
+            write!(f, "{}", self.message)?;
+        } else {
+            write!(f, "{}: {}", place.join(", "), self.message)?;
+        }
+        if !self.text.is_empty() {
+            write!(f, "\n  {}", self.text)?;
+            if self.column > 0 {
+                write!(f, "\n  {}^", " ".repeat(self.column - 1))?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl Error for ParseError {}
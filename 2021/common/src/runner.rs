@@ -0,0 +1,454 @@
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::bench::{bench_file, format_times, take_bench_flags, timed};
+use crate::framework::{check_stdin_flags, open_input, read_expected, take_switch, BaseDay};
+use crate::parse_error::ParseError;
+use crate::verify::{answer_diff, verify, Check};
+
+// Returns the solution for a day, or None if that day doesn't have one
+pub type DayMaker = fn(u32) -> Option<Box<dyn BaseDay>>;
+
+#[derive(Clone, Debug)]
+pub struct DayReport {
+    // None when run_day is running a day on its own, since it doesn't know which it is
+    pub day: Option<u32>,
+    pub file: String,
+    // empty if it didn't get through parse and setup, and None for a part that panicked
+    pub answers: Vec<Option<String>>,
+    pub expected: Vec<String>,
+    pub checks: Vec<Check>,
+    // how long each phase took, up to the one that panicked or didn't parse
+    pub times: Vec<Duration>,
+    pub panicked: bool,
+    pub parse_error: Option<ParseError>,
+}
+
+impl DayReport {
+    // whether it got through both parts, right or wrong
+    pub fn finished(&self) -> bool {
+        return !self.panicked && self.parse_error.is_none();
+    }
+
+    pub fn is_ok(&self) -> bool {
+        return self.finished() && !self.checks.contains(&Check::Fail);
+    }
+}
+
+// "all", a single day, a range like "3-7", or a list of those like "1,4,9-12"
+pub fn parse_days(spec: &str) -> Vec<u32> {
+    if spec == "all" {
+        return (1..=25).collect();
+    }
+    let mut days = Vec::new();
+    for part in spec.split(',') {
+        let (lo, hi) = match part.split_once('-') {
+            Some((lo, hi)) => (parse_day(lo), parse_day(hi)),
+            None => (parse_day(part), parse_day(part)),
+        };
+        if lo > hi {
+            panic!("Backwards day range: {}", part);
+        }
+        days.extend(lo..=hi);
+    }
+    return days;
+}
+
+fn parse_day(val: &str) -> u32 {
+    return match val.trim().parse::<u32>() {
+        Ok(day) if (1..=25).contains(&day) => day,
+        _ => panic!("Bad day: {}", val),
+    };
+}
+
+// A file with a slash in it (or "-" for stdin) is used as is, otherwise it's looked for in
+// the day's directory
+fn input_path(year_dir: &Path, day: u32, file: &str) -> String {
+    if file == "-" || file.contains('/') {
+        return file.to_string();
+    }
+    let path = year_dir.join(format!("day{:02}", day)).join(file);
+    return path.to_str().unwrap().to_string();
+}
+
+// only * and ?, which is all anyone types for these
+fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    return match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| glob_match(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && glob_match(&pattern[1..], &name[1..]),
+        Some(c) => name.first() == Some(c) && glob_match(&pattern[1..], &name[1..]),
+    };
+}
+
+pub fn is_pattern(spec: &str) -> bool {
+    return spec.contains('*') || spec.contains('?');
+}
+
+// The files to run for spec: a directory means every *.txt in it, and a glob (which can
+// only have wildcards in the file name) means every file it matches, but in both cases
+// only the ones that have a .expected to check against. Anything else is just the one
+// file.
+pub fn find_inputs(spec: &str) -> Vec<String> {
+    let path = Path::new(spec);
+    let (dir, pattern) = if path.is_dir() {
+        (path, "*.txt")
+    } else if is_pattern(spec) {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        (dir, path.file_name().unwrap().to_str().unwrap())
+    } else {
+        return vec![spec.to_string()];
+    };
+
+    let pattern = pattern.chars().collect::<Vec<char>>();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => panic!("Bad directory: {}", dir.display()),
+    };
+    let mut inputs = Vec::new();
+    for entry in entries {
+        let entry = entry.unwrap().path();
+        let name = entry.file_name().unwrap().to_str().unwrap();
+        if !glob_match(&pattern, &name.chars().collect::<Vec<char>>()) {
+            continue;
+        }
+        let fname = entry.to_str().unwrap().to_string();
+        if read_expected(&fname).is_some() {
+            inputs.push(fname);
+        }
+    }
+    inputs.sort();
+    return inputs;
+}
+
+// Runs both parts, keeping going to part 2 (with no answer for part 1) if part 1 panics.
+// If the input doesn't parse, it says where and doesn't run either part. With write,
+// answers that fname.expected doesn't have yet get added to it.
+pub fn run_file(
+    day_num: Option<u32>,
+    day: &mut dyn BaseDay,
+    fname: &str,
+    write: bool,
+) -> DayReport {
+    match day_num {
+        Some(day_num) => println!("== Day {:02}: {} ==", day_num, fname),
+        None => println!("== {} ==", fname),
+    }
+    let mut answers = Vec::new();
+    let mut times = Vec::new();
+    let started = panic::catch_unwind(AssertUnwindSafe(|| -> Result<(), ParseError> {
+        let mut input = open_input(fname);
+        let (parsed, parse_time) = timed(|| day.try_parse(&mut input));
+        times.push(parse_time);
+        parsed?;
+        let ((), setup_time) = timed(|| day.setup());
+        times.push(setup_time);
+        return Ok(());
+    }));
+    let parse_error = match started {
+        Ok(Err(ref err)) => {
+            println!("Parse error in {}, {}", fname, err);
+            Some(err.clone())
+        }
+        _ => None,
+    };
+
+    let mut panicked = started.is_err();
+    if let Ok(Ok(())) = started {
+        for part in 1..=2 {
+            let (result, time) = timed(|| {
+                panic::catch_unwind(AssertUnwindSafe(|| {
+                    if part == 1 {
+                        day.pt1()
+                    } else {
+                        day.pt2()
+                    }
+                }))
+            });
+            times.push(time);
+            match result {
+                Ok(res) => {
+                    println!("Result {}: {}", part, res);
+                    answers.push(Some(res));
+                }
+                Err(_) => {
+                    panicked = true;
+                    answers.push(None);
+                }
+            }
+        }
+        println!("Timing: {}", format_times(&times));
+    }
+
+    let checks = verify(fname, &answers, write);
+    let expected = read_expected(fname).unwrap_or_default();
+    return DayReport {
+        day: day_num,
+        file: fname.to_string(),
+        answers,
+        expected,
+        checks,
+        times,
+        panicked,
+        parse_error,
+    };
+}
+
+fn format_part(report: &DayReport, idx: usize) -> String {
+    let answer = match report.answers.get(idx) {
+        Some(Some(answer)) => answer,
+        Some(None) => return "panicked".to_string(),
+        None if report.panicked => return "panicked".to_string(),
+        None if report.parse_error.is_some() => return "parse error".to_string(),
+        None => return "-".to_string(),
+    };
+    // pictures are too wide to fit on the line
+    let shown = if answer.contains('/') {
+        format!("[{} rows]", answer.split('/').count())
+    } else {
+        answer.clone()
+    };
+    return match report.checks[idx] {
+        Check::Pass => format!("{} (pass)", shown),
+        Check::Fail => match answer_diff(answer, &report.expected[idx]) {
+            Some(_) => format!("{} (FAIL, see the diff above)", shown),
+            None => format!("{} (FAIL, expected {})", shown, report.expected[idx]),
+        },
+        Check::Unknown => format!("{} (?)", shown),
+    };
+}
+
+// Pads each column out to the widest thing in it
+fn print_table(rows: &Vec<Vec<String>>) {
+    let mut widths = vec![0; rows[0].len()];
+    for row in rows {
+        for (idx, cell) in row.iter().enumerate() {
+            widths[idx] = widths[idx].max(cell.chars().count());
+        }
+    }
+    for row in rows {
+        let cells = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<String>>();
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
+
+pub fn print_summary(reports: &Vec<DayReport>) {
+    let with_days = reports.iter().any(|r| r.day.is_some());
+    let mut rows = Vec::new();
+    let mut header = vec!["File", "Part 1", "Part 2", "Time"];
+    if with_days {
+        header.insert(0, "Day");
+    }
+    rows.push(
+        header
+            .into_iter()
+            .map(|h| h.to_string())
+            .collect::<Vec<String>>(),
+    );
+    for report in reports {
+        let file = Path::new(&report.file)
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap();
+        let mut row = vec![
+            file.to_string(),
+            format_part(report, 0),
+            format_part(report, 1),
+            format!("{:.1?}", report.times.iter().sum::<Duration>()),
+        ];
+        if with_days {
+            row.insert(
+                0,
+                report.day.map_or("".to_string(), |d| format!("{:02}", d)),
+            );
+        }
+        rows.push(row);
+    }
+
+    println!();
+    print_table(&rows);
+    let all_checks = reports.iter().flat_map(|r| r.checks.iter());
+    let count = |check: Check| all_checks.clone().filter(|c| **c == check).count();
+    println!(
+        "{} passed, {} failed, {} unknown, {} panicked, {} didn't parse",
+        count(Check::Pass),
+        count(Check::Fail),
+        count(Check::Unknown),
+        reports.iter().filter(|r| r.panicked).count(),
+        reports.iter().filter(|r| r.parse_error.is_some()).count()
+    );
+}
+
+// The main for a year's runner: run <year> <days> [file] [--write-expected]
+// [--bench N [--warmup M]], where days is anything parse_days takes and file defaults to
+// input.txt in each day's directory. The file can be a glob like '*.txt' to run every
+// input that has a .expected, or "-" to read from stdin. Benchmarking only happens for
+// files that got through their normal run.
+pub fn run_year(year: &str, year_dir: &Path, make_day: DayMaker) {
+    let mut args: Vec<String> = std::env::args().collect();
+    let bench_flags = take_bench_flags(&mut args);
+    let write = take_switch(&mut args, "--write-expected");
+    if args.len() < 3 || args.len() > 4 {
+        panic!(
+            "Usage: run {} <days> [file] [--write-expected] [--bench N [--warmup M]]",
+            year
+        );
+    }
+    if args[1] != year {
+        panic!("This runner only has {}, not {}", year, args[1]);
+    }
+    let days = parse_days(&args[2]);
+    let file = args.get(3).map_or("input.txt", |f| f.as_str());
+    check_stdin_flags(file, bench_flags.is_some(), write);
+    if file == "-" && days.len() > 1 {
+        panic!("Can only run one day when reading from stdin");
+    }
+
+    let mut reports = Vec::new();
+    for day_num in days {
+        match make_day(day_num) {
+            Some(_) => {
+                let spec = input_path(year_dir, day_num, file);
+                for fname in find_inputs(&spec) {
+                    let mut day = make_day(day_num).unwrap();
+                    let report = run_file(Some(day_num), &mut *day, &fname, write);
+                    if let (Some((runs, warmup)), true) = (bench_flags, report.finished()) {
+                        bench_file(&|| make_day(day_num).unwrap(), &fname, runs, warmup);
+                    }
+                    reports.push(report);
+                }
+            }
+            None => {
+                if args[2] != "all" {
+                    println!("No solution for day {}", day_num);
+                }
+            }
+        }
+    }
+
+    print_summary(&reports);
+    if !reports.iter().all(|r| r.is_ok()) {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str, name: &str) -> bool {
+        let pattern = pattern.chars().collect::<Vec<char>>();
+        return glob_match(&pattern, &name.chars().collect::<Vec<char>>());
+    }
+
+    #[test]
+    fn test_parse_days() {
+        assert_eq!(parse_days("all"), (1..=25).collect::<Vec<u32>>());
+        assert_eq!(parse_days("7"), vec![7]);
+        assert_eq!(parse_days("3-5"), vec![3, 4, 5]);
+        assert_eq!(parse_days("1, 4,9-10,25"), vec![1, 4, 9, 10, 25]);
+        assert_eq!(parse_days("6-6"), vec![6]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bad day: 26")]
+    fn test_day_out_of_range() {
+        parse_days("24-26");
+    }
+
+    #[test]
+    #[should_panic(expected = "Bad day: 0")]
+    fn test_day_zero() {
+        parse_days("0");
+    }
+
+    #[test]
+    #[should_panic(expected = "Bad day: x")]
+    fn test_day_not_a_number() {
+        parse_days("1,x");
+    }
+
+    #[test]
+    #[should_panic(expected = "Bad day: ")]
+    fn test_day_missing() {
+        parse_days("3-");
+    }
+
+    #[test]
+    #[should_panic(expected = "Backwards day range: 5-3")]
+    fn test_backwards_days() {
+        parse_days("5-3");
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob("*.txt", "input.txt"));
+        assert!(glob("*.txt", ".txt"));
+        assert!(!glob("*.txt", "input.txt.expected"));
+        assert!(glob("*", ""));
+        assert!(glob("**", "abc"));
+        assert!(glob("ex?.txt", "ex1.txt"));
+        assert!(!glob("ex?.txt", "ex.txt"));
+        assert!(!glob("ex?.txt", "ex12.txt"));
+        assert!(glob("a*b*c", "abxbc"));
+        assert!(!glob("a*b*c", "abxbcd"));
+        assert!(glob("", ""));
+        assert!(!glob("", "a"));
+        // ? is a whole character, not a byte
+        assert!(glob("caf?", "café"));
+    }
+
+    #[test]
+    fn test_input_path() {
+        let year = Path::new("2022");
+        assert_eq!(input_path(year, 3, "input.txt"), "2022/day03/input.txt");
+        assert_eq!(input_path(year, 3, "other/input.txt"), "other/input.txt");
+        assert_eq!(input_path(year, 3, "-"), "-");
+    }
+
+    #[test]
+    fn test_find_inputs() {
+        let dir = std::env::temp_dir().join(format!("find_inputs_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "a.txt",
+            "a.txt.expected",
+            "b.txt",
+            "c.dat",
+            "c.dat.expected",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+        // only the ones with a .expected
+        let found = find_inputs(dir.to_str().unwrap());
+        let by_glob = find_inputs(&path("*.dat"));
+        let by_char = find_inputs(&path("?.txt"));
+        let none = find_inputs(&path("*.csv"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found, vec![path("a.txt")]);
+        assert_eq!(by_glob, vec![path("c.dat")]);
+        assert_eq!(by_char, vec![path("a.txt")]);
+        assert!(none.is_empty());
+        // anything that isn't a directory or a pattern is used as is
+        assert_eq!(find_inputs("no/such/file.txt"), vec!["no/such/file.txt"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bad directory")]
+    fn test_find_inputs_bad_directory() {
+        find_inputs("/no/such/dir/*.txt");
+    }
+}
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::fmt::Debug;
 use std::hash::Hash;
 
@@ -60,28 +61,120 @@ impl FlagManager {
     }
 }
 
+// What a search found. If it never got to a finished state, states is empty and cost is
+// whatever max_cost or cant_solve gave.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Solution<S> {
+    pub cost: i32,
+    // from the start state to the finished one
+    pub states: Vec<S>,
+    // move_costs[i] is the cost of the move from states[i] to states[i + 1]
+    pub move_costs: Vec<i32>,
+    // how many states had their moves generated
+    pub states_expanded: usize,
+    // whether everything that might have led to a cheaper finish got looked at, so the cost
+    // is the minimum regardless of what the heuristic said. That's always true for dfs,
+    // and for astar it's true when the heuristic gave 0 for every state (so it was just
+    // dijkstra) or when it ran out of states without finding a solution.
+    pub exhaustive: bool,
+}
+
+impl<S> Solution<S>
+where
+    S: SolverState,
+{
+    fn unsolved(cost: i32) -> Solution<S> {
+        return Solution {
+            cost,
+            states: Vec::new(),
+            move_costs: Vec::new(),
+            states_expanded: 0,
+            exhaustive: false,
+        };
+    }
+
+    pub fn is_found(&self) -> bool {
+        return !self.states.is_empty();
+    }
+
+    // (from, cost, to) for each move along the way
+    pub fn moves(&self) -> Vec<(&S, i32, &S)> {
+        return self
+            .states
+            .windows(2)
+            .zip(self.move_costs.iter())
+            .map(|(w, c)| (&w[0], *c, &w[1]))
+            .collect();
+    }
+
+    // checks that every move is one gen_possible_moves really offers at that cost, that
+    // they add up to the total, and that the last state is finished
+    pub fn is_valid(&self, solver: &dyn SolverBase<S>) -> bool {
+        if !self.is_found() || !solver.is_finished(self.states.last().unwrap()) {
+            return false;
+        }
+        if self.move_costs.iter().sum::<i32>() != self.cost {
+            return false;
+        }
+        return self.moves().into_iter().all(|(from, cost, to)| {
+            solver
+                .gen_possible_moves(from)
+                .iter()
+                .any(|(c, s)| *c == cost && s == to)
+        });
+    }
+
+    fn record(&mut self, cost: i32, path: &[(i32, S)]) {
+        self.cost = cost;
+        self.states = path.iter().map(|(_, s)| s.clone()).collect();
+        self.move_costs = path[1..].iter().map(|(c, _)| *c).collect();
+    }
+}
+
 // return the minimum cost to reach a state for which is_finished is true, where transitions
 // from each state are given by gen_possible_moves
-pub fn cost_minimizing_dfs<S>(solver: &dyn SolverBase<S>, start_state: &S) -> i32
+pub fn cost_minimizing_dfs<S>(solver: &dyn SolverBase<S>, start_state: &S) -> Solution<S>
 where
     S: SolverState,
 {
     let mut cache = HashMap::new();
-    return cost_minimizing_dfs_recur(solver, start_state, 0, solver.max_cost(), &mut cache);
+    let mut path = vec![(0, start_state.clone())];
+    let mut found = Solution::unsolved(solver.max_cost());
+    // branches are only skipped once they cost more than the best so far
+    found.exhaustive = true;
+    cost_minimizing_dfs_recur(
+        solver,
+        start_state,
+        0,
+        solver.max_cost(),
+        &mut cache,
+        &mut path,
+        &mut found,
+    );
+    if solver.is_verbose() && found.is_found() {
+        solver.print_path(&found.states);
+    }
+    return found;
 }
 
+// path is the moves taken to get to state, starting with (0, start state)
 fn cost_minimizing_dfs_recur<S>(
     solver: &dyn SolverBase<S>,
     state: &S,
     cur_cost: i32,
     best_cost: i32,
     cache: &mut HashMap<S, (i32, i32)>,
+    path: &mut Vec<(i32, S)>,
+    found: &mut Solution<S>,
 ) -> i32
 where
     S: SolverState,
 {
     if solver.is_finished(state) {
         println!("Found finish state with cost {}", cur_cost);
+        if !found.is_found() || cur_cost < found.cost {
+            found.record(cur_cost, path);
+        }
         return cur_cost;
     }
 
@@ -96,6 +189,7 @@ where
     }
 
     let mut possible_moves = solver.gen_possible_moves(state);
+    found.states_expanded += 1;
     possible_moves.sort();
     if solver.is_verbose() {
         println!("For {:?}, generated {} moves", state, possible_moves.len());
@@ -107,12 +201,14 @@ where
     }
 
     let mut pbest = best_cost;
-    for (pcost, pstate) in &possible_moves {
-        let pcur = cur_cost + *pcost;
+    for (pcost, pstate) in possible_moves {
+        let pcur = cur_cost + pcost;
         if pcur > pbest {
             continue;
         }
-        let ptot = cost_minimizing_dfs_recur(solver, pstate, pcur, pbest, cache);
+        path.push((pcost, pstate.clone()));
+        let ptot = cost_minimizing_dfs_recur(solver, &pstate, pcur, pbest, cache, path, found);
+        path.pop();
         if ptot <= pbest {
             pbest = ptot;
         }
@@ -123,18 +219,28 @@ where
     return pbest;
 }
 
-pub fn count_all_paths_dfs<S>(solver: &dyn SolverBase<S>, start_state: &S) -> i32
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct PathCount {
+    pub count: i32,
+    // how many states had their moves generated
+    pub states_expanded: usize,
+}
+
+pub fn count_all_paths_dfs<S>(solver: &dyn SolverBase<S>, start_state: &S) -> PathCount
 where
     S: SolverState,
 {
     let mut cache = HashMap::new();
-    return count_all_paths_dfs_recur(solver, start_state, &mut cache);
+    let mut states_expanded = 0;
+    let count = count_all_paths_dfs_recur(solver, start_state, &mut cache, &mut states_expanded);
+    return PathCount { count, states_expanded };
 }
 
 fn count_all_paths_dfs_recur<S>(
     solver: &dyn SolverBase<S>,
     state: &S,
     cache: &mut HashMap<S, i32>,
+    states_expanded: &mut usize,
 ) -> i32
 where
     S: SolverState,
@@ -152,6 +258,7 @@ where
     }
 
     let possible_moves = solver.gen_possible_moves(state);
+    *states_expanded += 1;
     // don't need to sort here since we're trying everything
     if solver.is_verbose() {
         println!("For {:?}, generated {} moves", state, possible_moves.len());
@@ -164,7 +271,7 @@ where
 
     let mut count = 0;
     for (_pcost, pstate) in &possible_moves {
-        count += count_all_paths_dfs_recur(solver, pstate, cache);
+        count += count_all_paths_dfs_recur(solver, pstate, cache, states_expanded);
     }
 
     cache.insert(state.clone(), count);
@@ -172,38 +279,160 @@ where
     return count;
 }
 
-// TODO: make this a-star
-pub fn cost_minimizing_bfs<S>(solver: &dyn SolverBase<S>, start_state: &S) -> i32
+// return the minimum cost to reach a state for which is_finished is true, always expanding
+// the state with the lowest cost so far plus heuristic estimate of the cost still to go.
+// With the default heuristic of 0 this is just dijkstra; a heuristic that ever
+// over-estimates can give a cost that isn't the minimum.
+pub fn cost_minimizing_astar<S>(solver: &dyn SolverBase<S>, start_state: &S) -> Solution<S>
 where
     S: SolverState,
 {
-    let mut working = Vec::new();
-    working.push((start_state.clone(), 0));
-    while !working.is_empty() {
-        let (cur, cost) = working.remove(0);
+    let is_verbose = solver.is_verbose();
+    let mut best_costs = HashMap::new();
+    let mut came_from: HashMap<S, (i32, S)> = HashMap::new();
+    let mut working = BinaryHeap::new();
+    let mut states_expanded = 0;
+    // whether the heuristic ever said anything but 0, in which case it might have led
+    // the search away from the cheapest finish
+    let mut used_heuristic = false;
+    let mut estimate = |state: &S| -> i32 {
+        let guess = solver.heuristic(state);
+        used_heuristic |= guess != 0;
+        return guess;
+    };
+    best_costs.insert(start_state.clone(), 0);
+    working.push(Reverse((estimate(start_state), 0, start_state.clone())));
+
+    while let Some(Reverse((_, cost, cur))) = working.pop() {
+        if cost > best_costs[&cur] {
+            // found a cheaper way here after this was queued
+            continue;
+        }
         if solver.is_finished(&cur) {
-            return cost;
+            let mut found = Solution::unsolved(cost);
+            found.record(cost, &reconstruct_path(&came_from, &cur));
+            found.states_expanded = states_expanded;
+            found.exhaustive = !used_heuristic;
+            if is_verbose {
+                solver.print_path(&found.states);
+            }
+            return found;
         }
 
         let possible_moves = solver.gen_possible_moves(&cur);
-        if solver.is_verbose() {
+        states_expanded += 1;
+        if is_verbose {
             println!("For {:?}, generated {} moves", cur, possible_moves.len());
         }
-        working.extend(possible_moves.into_iter().map(|(c, s)| (s, c + cost)));
+
+        for (pcost, pstate) in possible_moves {
+            let pcur = cost + pcost;
+            if let Some(existing_cost) = best_costs.get(&pstate) {
+                if pcur >= *existing_cost {
+                    continue;
+                }
+            }
+            best_costs.insert(pstate.clone(), pcur);
+            came_from.insert(pstate.clone(), (pcost, cur.clone()));
+            working.push(Reverse((pcur + estimate(&pstate), pcur, pstate)));
+        }
     }
 
-    panic!("Couldn't find solution?");
+    let mut unsolved = Solution::unsolved(solver.cant_solve());
+    unsolved.states_expanded = states_expanded;
+    unsolved.exhaustive = true;
+    return unsolved;
 }
 
-pub trait SolverBase<S> {
+// the moves to get to end, in the same form as Solution::record takes them
+fn reconstruct_path<S>(came_from: &HashMap<S, (i32, S)>, end: &S) -> Vec<(i32, S)>
+where
+    S: SolverState,
+{
+    let mut path = Vec::new();
+    let mut cur = end.clone();
+    while let Some((cost, prev)) = came_from.get(&cur) {
+        path.push((*cost, cur));
+        cur = prev.clone();
+    }
+    path.push((0, cur));
+    path.reverse();
+    return path;
+}
+
+pub trait SolverBase<S>
+where
+    S: SolverState,
+{
     fn is_finished(&self, state: &S) -> bool;
     fn print_state(&self, state: &S) -> ();
     // returns a list of (cost, new state) pairs
     fn gen_possible_moves(&self, state: &S) -> Vec<(i32, S)>;
     fn is_verbose(&self) -> bool;
+    fn print_path(&self, path: &Vec<S>) {
+        println!("Final path: {:?}", path);
+    }
     fn max_cost(&self) -> i32 {
         return i32::MAX - 1;
     }
+    // estimate of the cost from here to a finished state, for cost_minimizing_astar.
+    // it should never be more than the real cost
+    fn heuristic(&self, _state: &S) -> i32 {
+        return 0;
+    }
+    fn cant_solve(&self) -> i32 {
+        panic!("Couldn't find solution?");
+    }
 }
 
 pub trait SolverState: Eq + Hash + Ord + PartialOrd + Debug + Clone {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl SolverState for i32 {}
+
+    // counting up to 4, either by 1 for 1 or by 2 for 3
+    struct Counter {
+        guess: bool,
+    }
+
+    impl SolverBase<i32> for Counter {
+        fn is_finished(&self, state: &i32) -> bool {
+            return *state == 4;
+        }
+        fn print_state(&self, _state: &i32) -> () {}
+        fn gen_possible_moves(&self, state: &i32) -> Vec<(i32, i32)> {
+            if *state >= 4 {
+                return vec![];
+            }
+            return vec![(1, state + 1), (3, state + 2)];
+        }
+        fn is_verbose(&self) -> bool {
+            return false;
+        }
+        fn heuristic(&self, state: &i32) -> i32 {
+            return if self.guess { 4 - state } else { 0 };
+        }
+    }
+
+    #[test]
+    fn test_astar_exhaustive() {
+        let solution = cost_minimizing_astar(&Counter { guess: false }, &0);
+        assert_eq!((solution.cost, solution.states.len()), (4, 5));
+        assert!(solution.exhaustive);
+        assert!(solution.is_valid(&Counter { guess: false }));
+
+        let solution = cost_minimizing_astar(&Counter { guess: true }, &0);
+        assert_eq!(solution.cost, 4);
+        assert!(!solution.exhaustive);
+    }
+
+    #[test]
+    fn test_dfs_exhaustive() {
+        let solution = cost_minimizing_dfs(&Counter { guess: false }, &0);
+        assert_eq!(solution.cost, 4);
+        assert!(solution.exhaustive);
+    }
+}
@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::Path;
+
+use crate::bench::run_phases;
+use crate::framework::{read_expected, BaseDay};
+
+// Makes a #[test] for each input named (small for small.txt and so on), which runs the day
+// on it and checks both parts against its .expected, skipping any part that doesn't have an
+// expected answer. Attributes can go in front of a name, eg for ones that are too slow:
+//
+//     common::expected_tests!(make_day, small, small2, #[ignore] input);
+#[macro_export]
+macro_rules! expected_tests {
+    ($make_day:ident, $($(#[$meta:meta])* $name:ident),+ $(,)?) => {
+        #[cfg(test)]
+        mod expected_tests {
+            $(
+                $(#[$meta])*
+                #[test]
+                fn $name() {
+                    let fname = $crate::testing::input_path(
+                        env!("CARGO_MANIFEST_DIR"),
+                        file!(),
+                        concat!(stringify!($name), ".txt"),
+                    );
+                    $crate::testing::check_expected(super::$make_day, &fname);
+                }
+            )+
+        }
+    };
+}
+
+// The inputs are in the directory above the one with the source file in it. file!() is
+// relative to something above the manifest directory when building in a workspace, and
+// goes through the day's directory when the runner includes the day as a module, so this
+// looks for wherever it really is.
+pub fn input_path(manifest_dir: &str, source_file: &str, fname: &str) -> String {
+    let source = Path::new(manifest_dir)
+        .ancestors()
+        .map(|dir| dir.join(source_file))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| panic!("Can't find {} from {}", source_file, manifest_dir));
+    let day_dir = source.parent().unwrap().parent().unwrap();
+    return day_dir.join(fname).to_str().unwrap().to_string();
+}
+
+pub fn check_expected(make_day: fn() -> Box<dyn BaseDay>, fname: &str) {
+    let expected = read_expected(fname).unwrap_or_else(|| panic!("No {}.expected", fname));
+    let input = match fs::read(fname) {
+        Ok(input) => input,
+        Err(_) => panic!("Bad file: {}", fname),
+    };
+    let (answers, _) = run_phases(&make_day, &input);
+    for (idx, answer) in answers.iter().enumerate() {
+        if !expected[idx].is_empty() {
+            assert_eq!(answer, &expected[idx], "Part {} of {}", idx + 1, fname);
+        }
+    }
+}
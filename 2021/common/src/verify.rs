@@ -0,0 +1,226 @@
+use std::fs;
+
+use crate::framework::read_expected;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Check {
+    Pass,
+    Fail,
+    // there's no expected answer to check against
+    Unknown,
+}
+
+pub fn check_answer(answer: &str, expected: Option<&String>) -> Check {
+    return match expected {
+        Some(exp) if !exp.is_empty() => {
+            if answer == exp {
+                Check::Pass
+            } else {
+                Check::Fail
+            }
+        }
+        _ => Check::Unknown,
+    };
+}
+
+// A part that panicked (None) fails if there's an answer it should have given
+pub fn check_answers(answers: &[Option<String>], expected: &[String]) -> Vec<Check> {
+    return answers
+        .iter()
+        .enumerate()
+        .map(|(idx, answer)| match (answer, expected.get(idx)) {
+            (Some(answer), exp) => check_answer(answer, exp),
+            (None, Some(exp)) if !exp.is_empty() => Check::Fail,
+            (None, _) => Check::Unknown,
+        })
+        .collect();
+}
+
+// Answers that are pictures (like 2022 day10's CRT) come back with their rows joined by
+// '/', so this splits them up again and shows which rows differ, with a ^ under each
+// character that's wrong. Returns None for answers that are just one line.
+pub fn answer_diff(answer: &str, expected: &str) -> Option<String> {
+    let got: Vec<&str> = answer.split('/').collect();
+    let want: Vec<&str> = expected.split('/').collect();
+    if got.len() == 1 && want.len() == 1 {
+        return None;
+    }
+    let mut lines = Vec::new();
+    for idx in 0..got.len().max(want.len()) {
+        match (want.get(idx), got.get(idx)) {
+            (Some(w), Some(g)) if w == g => lines.push(format!("  {}", g)),
+            (w, g) => {
+                if let Some(w) = w {
+                    lines.push(format!("- {}", w));
+                }
+                if let Some(g) = g {
+                    lines.push(format!("+ {}", g));
+                }
+                if let (Some(w), Some(g)) = (w, g) {
+                    let mut wc = w.chars();
+                    let mut gc = g.chars();
+                    let mut marks = String::from("  ");
+                    for _ in 0..w.chars().count().max(g.chars().count()) {
+                        marks.push(if wc.next() == gc.next() { ' ' } else { '^' });
+                    }
+                    lines.push(marks.trim_end().to_string());
+                }
+            }
+        }
+    }
+    return Some(lines.join("\n"));
+}
+
+// eg "Part 1: pass", or for a failure, what was expected (or a diff if it's a picture).
+// answer is None if the part panicked.
+pub fn describe_part(
+    part: usize,
+    answer: Option<&str>,
+    check: Check,
+    expected: Option<&String>,
+) -> String {
+    let answer = match answer {
+        Some(answer) => answer,
+        None => return format!("Part {}: panicked", part),
+    };
+    return match check {
+        Check::Pass => format!("Part {}: pass", part),
+        Check::Unknown => format!("Part {}: no expected answer", part),
+        Check::Fail => {
+            let expected = expected.unwrap();
+            match answer_diff(answer, expected) {
+                Some(diff) => format!("Part {}: FAIL\n{}", part, diff),
+                None => format!("Part {}: FAIL, expected {}", part, expected),
+            }
+        }
+    };
+}
+
+// Fills in fname.expected with any answers it doesn't have yet, leaving the ones it does
+// have alone, and skipping parts that panicked (or have an empty answer, since an empty
+// line in there means it isn't known). Returns whether anything got written.
+pub fn write_expected(fname: &str, answers: &[Option<String>]) -> bool {
+    let mut expected = read_expected(fname).unwrap_or_default();
+    let mut changed = false;
+    for (idx, answer) in answers.iter().enumerate() {
+        let answer = match answer {
+            Some(answer) if !answer.is_empty() => answer,
+            _ => continue,
+        };
+        while expected.len() <= idx {
+            expected.push("".to_string());
+        }
+        if expected[idx].is_empty() {
+            expected[idx] = answer.clone();
+            changed = true;
+        }
+    }
+    if changed {
+        while expected.last().is_some_and(|e| e.is_empty()) {
+            expected.pop();
+        }
+        let mut contents = expected.join("\n");
+        contents.push('\n');
+        fs::write(fname.to_owned() + ".expected", contents).unwrap();
+    }
+    return changed;
+}
+
+// Checks the answers against fname.expected and prints how each part did, writing any
+// that aren't known yet into it if asked to
+pub fn verify(fname: &str, answers: &[Option<String>], write: bool) -> Vec<Check> {
+    let expected = read_expected(fname).unwrap_or_default();
+    let checks = check_answers(answers, &expected);
+    for (idx, (answer, check)) in answers.iter().zip(checks.iter()).enumerate() {
+        println!(
+            "{}",
+            describe_part(idx + 1, answer.as_deref(), *check, expected.get(idx))
+        );
+    }
+    if write && write_expected(fname, answers) {
+        println!("Wrote new answers to {}.expected", fname);
+    }
+    return checks;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A path for an input that doesn't exist, whose .expected gets cleaned up after
+    fn scratch(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("verify_{}_{}", std::process::id(), name));
+        return path.to_str().unwrap().to_string();
+    }
+
+    fn answers(vals: &[Option<&str>]) -> Vec<Option<String>> {
+        return vals.iter().map(|val| val.map(|v| v.to_string())).collect();
+    }
+
+    #[test]
+    fn test_answer_diff() {
+        assert_eq!(answer_diff("12", "13"), None);
+        assert_eq!(
+            answer_diff("#.#/###", "#.#/#.#").unwrap(),
+            "  #.#\n- #.#\n+ ###\n   ^"
+        );
+        // a row that's missing or extra just shows up on its own
+        assert_eq!(
+            answer_diff("ab/cd/ef", "ab/cd").unwrap(),
+            "  ab\n  cd\n+ ef"
+        );
+        assert_eq!(answer_diff("ab", "ab/cd").unwrap(), "  ab\n- cd");
+    }
+
+    #[test]
+    fn test_check_answers() {
+        let expected = vec!["1".to_string(), "".to_string(), "3".to_string()];
+        let checks = check_answers(&answers(&[Some("1"), Some("2"), None, None]), &expected);
+        assert_eq!(
+            checks,
+            vec![Check::Pass, Check::Unknown, Check::Fail, Check::Unknown]
+        );
+
+        let two = "2".to_string();
+        assert_eq!(
+            describe_part(2, None, Check::Unknown, None),
+            "Part 2: panicked"
+        );
+        assert_eq!(
+            describe_part(1, Some(""), Check::Fail, Some(&two)),
+            "Part 1: FAIL, expected 2"
+        );
+        assert_eq!(
+            describe_part(1, Some("2"), Check::Pass, Some(&two)),
+            "Part 1: pass"
+        );
+    }
+
+    #[test]
+    fn test_write_new_expected() {
+        let fname = scratch("new");
+        let expected = fname.clone() + ".expected";
+        // nothing to write
+        assert!(!write_expected(&fname, &answers(&[None, Some("")])));
+        assert!(fs::metadata(&expected).is_err());
+
+        assert!(write_expected(&fname, &answers(&[Some("1"), None])));
+        assert_eq!(fs::read_to_string(&expected).unwrap(), "1\n");
+        // the part that's known stays as it is
+        assert!(write_expected(&fname, &answers(&[Some("9"), Some("2")])));
+        assert_eq!(fs::read_to_string(&expected).unwrap(), "1\n2\n");
+        assert!(!write_expected(&fname, &answers(&[Some("9"), Some("8")])));
+        fs::remove_file(&expected).unwrap();
+    }
+
+    #[test]
+    fn test_write_existing_expected() {
+        let fname = scratch("existing");
+        let expected = fname.clone() + ".expected";
+        fs::write(&expected, "\n5\n").unwrap();
+        assert!(write_expected(&fname, &answers(&[Some("3"), Some("7")])));
+        let written = fs::read_to_string(&expected).unwrap();
+        fs::remove_file(&expected).unwrap();
+        assert_eq!(written, "3\n5\n");
+    }
+}
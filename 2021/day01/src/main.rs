@@ -28,7 +28,12 @@ impl BaseDay for Day01 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day01 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day01 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
@@ -79,7 +79,12 @@ impl BaseDay for Day03 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day03 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day03 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
@@ -79,7 +79,12 @@ impl BaseDay for Day10 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day10 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day10 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
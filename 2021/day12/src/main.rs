@@ -54,7 +54,7 @@ fn find_paths_dfs(
         seen_mgr: seen_mgr,
     };
 
-    return count_all_paths_dfs(&recurser, &state);
+    return count_all_paths_dfs(&recurser, &state).count;
 }
 
 #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -149,7 +149,12 @@ impl BaseDay for Day12 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day12 { cxns: HashMap::new() });
+}
+
 fn main() {
-    let mut day = Day12 { cxns: HashMap::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, small2, small3, input);
@@ -4,8 +4,9 @@ use lazy_regex::regex;
 
 extern crate common;
 
-use common::framework::{parse_lines, run_day, BaseDay, InputReader};
+use common::framework::{run_day, try_parse_vals, BaseDay, InputReader};
 use common::grid::{print_grid, Coord, Grid};
+use common::parse_error::ParseError;
 
 #[derive(Copy, Clone)]
 enum Fold {
@@ -64,32 +65,38 @@ fn fold_points(points: &HashSet<Coord>, fold: Fold) -> HashSet<Coord> {
 
 impl BaseDay for Day13 {
     fn parse(&mut self, input: &mut InputReader) {
-        let mut parse_coord = |line: String| -> Coord {
-            let sep = regex!(r#"\s*,\s*"#);
-            let pieces: Vec<&str> = sep.split(&line).collect();
-            return Coord {
-                x: pieces[0].parse::<i32>().unwrap(),
-                y: pieces[1].parse::<i32>().unwrap(),
-            };
+        self.try_parse(input)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    fn try_parse(&mut self, input: &mut InputReader) -> Result<(), ParseError> {
+        let mut parse_coord = |line: String| -> Result<Coord, ParseError> {
+            match &try_parse_vals::<i32>(&line)?[..] {
+                [x, y] => return Ok(Coord { x: *x, y: *y }),
+                _ => return Err(ParseError::new("Expected x,y")),
+            }
         };
 
-        let mut parse_fold = |line: String| -> Fold {
+        let mut parse_fold = |line: String| -> Result<Fold, ParseError> {
             let rex = regex!(r#"fold along (x|y)=(\d+)"#);
             match rex.captures(&line) {
                 Some(c) => {
                     let val = c[2].parse::<i32>().unwrap();
                     match &c[1] {
-                        "x" => return Fold::X(val),
-                        "y" => return Fold::Y(val),
-                        _ => panic!("Bad fold: {}", &line),
+                        "x" => return Ok(Fold::X(val)),
+                        "y" => return Ok(Fold::Y(val)),
+                        _ => return Err(ParseError::new("Bad fold")),
                     };
                 }
-                None => panic!("Bad line: {}", &line),
+                None => return Err(ParseError::new("Bad fold")),
             }
         };
 
-        self.points.extend(parse_lines(input, &mut parse_coord));
-        self.folds = parse_lines(input, &mut parse_fold);
+        let mut sections = input.sections();
+        self.points
+            .extend(sections.lines("points", &mut parse_coord)?);
+        self.folds = sections.lines("folds", &mut parse_fold)?;
+        return Ok(());
     }
 
     fn pt1(&mut self) -> String {
@@ -108,7 +115,12 @@ impl BaseDay for Day13 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day13 { points: HashSet::new(), folds: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day13 { points: HashSet::new(), folds: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
@@ -98,7 +98,12 @@ impl BaseDay for Day14 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day14 { template: "".to_string(), rules: HashMap::new() });
+}
+
 fn main() {
-    let mut day = Day14 { template: "".to_string(), rules: HashMap::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
@@ -1,11 +1,8 @@
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
-use std::collections::HashMap;
-
 extern crate common;
 
 use common::framework::{parse_grid, run_day, BaseDay, InputReader};
 use common::grid::{four_neighbors, Coord, Grid};
+use common::solver::{cost_minimizing_astar, SolverBase};
 
 struct Day15 {
     vals: Grid<i32>,
@@ -28,55 +25,42 @@ fn eff_risk(grid: &Grid<i32>, coord: Coord, mult: i32) -> Option<i32> {
         .map(|v| ((*v + rb - 1) % 9) + 1);
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
-struct State {
-    estimate: i32,
-    coord: Coord,
+struct Cave<'a> {
+    grid: &'a Grid<i32>,
+    end: Coord,
+    mult: i32,
 }
 
-impl Ord for State {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other
-            .estimate
-            .cmp(&self.estimate)
-            .then_with(|| self.coord.cmp(&other.coord))
+impl SolverBase<Coord> for Cave<'_> {
+    fn is_finished(&self, state: &Coord) -> bool {
+        return *state == self.end;
+    }
+
+    fn print_state(&self, state: &Coord) {
+        println!("{:?}", state);
     }
-}
 
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    // returns a list of (cost, new state) pairs
+    fn gen_possible_moves(&self, state: &Coord) -> Vec<(i32, Coord)> {
+        return four_neighbors(state)
+            .into_iter()
+            .filter_map(|ngh| eff_risk(self.grid, ngh, self.mult).map(|risk| (risk, ngh)))
+            .collect();
+    }
+
+    fn is_verbose(&self) -> bool {
+        return false;
+    }
+
+    // every risk is at least 1, so this never over-estimates
+    fn heuristic(&self, state: &Coord) -> i32 {
+        return (self.end.x - state.x) + (self.end.y - state.y);
     }
 }
 
 fn compute_costs(grid: &Grid<i32>, start: Coord, end: Coord, mult: i32) -> i32 {
-    let mut costs: HashMap<Coord, i32> = HashMap::new();
-
-    let heuristic = |c: &Coord| -> i32 { (end.x - c.x) + (end.y - c.y) };
-
-    costs.insert(start, 0);
-    let mut working = BinaryHeap::new();
-    working.push(State { estimate: heuristic(&start), coord: start.clone() });
-    while let Some(cur) = working.pop() {
-        let cur_cost = *costs.get(&cur.coord).unwrap_or(&i32::MAX);
-        for ngh in four_neighbors(&cur.coord) {
-            if let Some(ngh_risk) = eff_risk(&grid, ngh, mult) {
-                let existing_cost = *costs.get(&ngh).unwrap_or(&i32::MAX);
-                let via_cur_cost = cur_cost + ngh_risk;
-                if via_cur_cost < existing_cost {
-                    // heuristic never over-estimates, so we can return as soon as we
-                    // find the end
-                    if ngh == end {
-                        return via_cur_cost;
-                    }
-                    // found a cheaper way to get to ngh, so have to recalc it
-                    costs.insert(ngh, via_cur_cost);
-                    working.push(State { estimate: via_cur_cost + heuristic(&ngh), coord: ngh });
-                }
-            }
-        }
-    }
-    panic!("Never found end?");
+    let cave = Cave { grid, end, mult };
+    return cost_minimizing_astar(&cave, &start).cost;
 }
 
 impl BaseDay for Day15 {
@@ -104,7 +88,12 @@ impl BaseDay for Day15 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day15 { vals: Grid::new() });
+}
+
 fn main() {
-    let mut day = Day15 { vals: Grid::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
@@ -188,7 +188,12 @@ impl BaseDay for Day16 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day16 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day16 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, small2, small3, input);
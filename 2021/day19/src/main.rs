@@ -1,12 +1,11 @@
 use std::collections::{HashMap, HashSet};
-use std::io::BufRead;
 
 use itertools::Itertools;
 use lazy_regex::regex;
 
 extern crate common;
 
-use common::framework::{run_day, BaseDay, InputReader};
+use common::framework::{parse_records_by, run_day, BaseDay, InputReader, Record, RecordSep};
 use common::grid3d::{
     add_direction3d, find_direction3d, manhattan3d, sub_direction3d, Coord3d, Direction3d,
 };
@@ -193,34 +192,29 @@ impl BaseDay for Day19 {
         let header_rex = regex!(r#"--- (.*?) ---"#);
         let coord_rex = regex!(r#"(-?\d+)\s*,\s*(-?\d+)\s*,\s*(-?\d+)"#);
 
-        let mut cur: Option<Scanner> = None;
-        for line in input.lines() {
-            let line = line.unwrap();
-            if line.is_empty() {
-                if cur.is_some() {
-                    self.vals.push(cur.unwrap());
-                    cur = None;
-                }
-            } else if let Some(c) = header_rex.captures(&line) {
-                cur = Some(Scanner { name: c[1].to_owned(), beacons: Vec::new() });
-            } else if let Some(c) = coord_rex.captures(&line) {
-                let coord = Coord3d {
-                    x: c[1].parse::<i32>().unwrap(),
-                    y: c[2].parse::<i32>().unwrap(),
-                    z: c[3].parse::<i32>().unwrap(),
+        self.vals = parse_records_by(
+            input,
+            &RecordSep::Header(header_rex),
+            &mut |record: &Record| {
+                let name = match &record.header {
+                    Some(header) => header_rex.captures(header).unwrap()[1].to_owned(),
+                    None => panic!("No active scanner for coords: {:?}", record.lines),
                 };
-                if let Some(ref mut scanner) = cur {
-                    scanner.beacons.push(coord);
-                } else {
-                    panic!("No active scanner for coord: {:?}", coord);
-                }
-            } else {
-                panic!("Bad line: {}", line);
-            }
-        }
-        if cur.is_some() {
-            self.vals.push(cur.unwrap());
-        }
+                let beacons = record
+                    .lines
+                    .iter()
+                    .map(|line| match coord_rex.captures(line) {
+                        Some(c) => Coord3d {
+                            x: c[1].parse::<i32>().unwrap(),
+                            y: c[2].parse::<i32>().unwrap(),
+                            z: c[3].parse::<i32>().unwrap(),
+                        },
+                        None => panic!("Bad line: {}", line),
+                    })
+                    .collect();
+                return Scanner { name, beacons };
+            },
+        );
     }
 
     fn setup(&mut self) {
@@ -272,11 +266,16 @@ impl BaseDay for Day19 {
     }
 }
 
-fn main() {
-    let mut day = Day19 {
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day19 {
         vals: Vec::new(),
         orientations: Vec::new(),
         all_beacons: HashSet::new(),
-    };
-    run_day(&mut day);
+    });
+}
+
+fn main() {
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
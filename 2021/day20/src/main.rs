@@ -1,86 +1,52 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
 extern crate common;
 
+use common::automaton::Automaton;
 use common::framework::{parse_grid, parse_lines, run_day, BaseDay, InputReader};
-use common::grid::{Coord, Grid};
+use common::grid::{print_grid, Coord};
 
 struct Day20 {
     algo: HashSet<i32>,
-    image: HashMap<Coord, bool>,
+    image: Automaton<Coord, bool>,
 }
 
 fn enhance(
-    image: &HashMap<Coord, bool>,
+    image: &Automaton<Coord, bool>,
     algo: &HashSet<i32>,
-    rounds: i32,
+    rounds: usize,
     verbose: bool,
-) -> HashMap<Coord, bool> {
+) -> Automaton<Coord, bool> {
     let mut cur = image.clone();
 
-    fn flip(bit: u8, coord: Coord, image: &HashMap<Coord, bool>, default: bool) -> i32 {
-        if *image.get(&coord).unwrap_or(&default) {
-            return 1 << bit;
-        } else {
-            return 0;
+    // the 3x3 square around the coord, in the order the bits of the key go in
+    let mut square = |coord: &Coord| -> Vec<Coord> {
+        let mut ret = Vec::new();
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                ret.push(Coord { x: coord.x + dx, y: coord.y + dy });
+            }
         }
-    }
-
-    let get_default = |round: i32| -> bool {
-        return algo.contains(&0) && round % 2 == 1;
+        return ret;
+    };
+    let mut rule = |_c: &Coord, _v: &bool, square: &[&bool]| -> bool {
+        let key = square
+            .iter()
+            .fold(0, |key, lit| (key << 1) | (**lit as i32));
+        return algo.contains(&key);
     };
 
-    for round in 0..rounds {
-        let min_x = cur.keys().map(|c| c.x).min().unwrap();
-        let min_y = cur.keys().map(|c| c.y).min().unwrap();
-        let max_x = cur.keys().map(|c| c.x).max().unwrap();
-        let max_y = cur.keys().map(|c| c.y).max().unwrap();
-
-        let mut nxt = HashMap::new();
-        for x in min_x - 1..max_x + 2 {
-            for y in min_y - 1..max_y + 2 {
-                let mut key = 0;
-                key |= flip(8, Coord { x: x - 1, y: y - 1 }, &cur, get_default(round));
-                key |= flip(7, Coord { x: x + 0, y: y - 1 }, &cur, get_default(round));
-                key |= flip(6, Coord { x: x + 1, y: y - 1 }, &cur, get_default(round));
-                key |= flip(5, Coord { x: x - 1, y: y + 0 }, &cur, get_default(round));
-                key |= flip(4, Coord { x: x + 0, y: y + 0 }, &cur, get_default(round));
-                key |= flip(3, Coord { x: x + 1, y: y + 0 }, &cur, get_default(round));
-                key |= flip(2, Coord { x: x - 1, y: y + 1 }, &cur, get_default(round));
-                key |= flip(1, Coord { x: x + 0, y: y + 1 }, &cur, get_default(round));
-                key |= flip(0, Coord { x: x + 1, y: y + 1 }, &cur, get_default(round));
-                if key < 0 || key >= 512 {
-                    panic!("Bad key: {}", key);
-                }
-                if algo.contains(&key) {
-                    nxt.insert(Coord { x: x, y: y }, true);
-                } else {
-                    nxt.insert(Coord { x: x, y: y }, false);
-                }
-            }
-        }
-        cur = nxt;
+    for _ in 0..rounds {
+        cur.step(&mut square, &mut rule);
 
         if verbose {
-            let min_x = cur.keys().map(|c| c.x).min().unwrap();
-            let min_y = cur.keys().map(|c| c.y).min().unwrap();
-            let max_x = cur.keys().map(|c| c.x).max().unwrap();
-            let max_y = cur.keys().map(|c| c.y).max().unwrap();
-
-            for y in min_y - 1..max_y + 2 {
-                for x in min_x - 1..max_x + 2 {
-                    if *cur
-                        .get(&Coord { x: x, y: y })
-                        .unwrap_or(&(get_default(round)))
-                    {
-                        print!("#");
-                    } else {
-                        print!(".");
-                    }
+            print_grid(&cur.to_grid(), &mut |c: &Coord, _v: Option<&bool>| {
+                if *cur.get(c) {
+                    "#".to_string()
+                } else {
+                    ".".to_string()
                 }
-                println!();
-            }
-            println!();
+            });
             println!();
         }
     }
@@ -110,21 +76,30 @@ impl BaseDay for Day20 {
                 Some(false)
             }
         }
-        self.image = parse_grid(input, &mut parse_coord).coords;
+        let grid = parse_grid(input, &mut parse_coord);
+        self.image = Automaton::from_grid(&grid, false, Coord { x: 0, y: 0 });
     }
 
     fn pt1(&mut self) -> String {
         let enhanced = enhance(&self.image, &self.algo, 2, false);
-        return enhanced.values().filter(|v| **v).count().to_string();
+        return enhanced.count(&true).to_string();
     }
 
     fn pt2(&mut self) -> String {
         let enhanced = enhance(&self.image, &self.algo, 50, false);
-        return enhanced.values().filter(|v| **v).count().to_string();
+        return enhanced.count(&true).to_string();
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day20 {
+        algo: HashSet::new(),
+        image: Automaton::new(false, Coord { x: 0, y: 0 }),
+    });
+}
+
 fn main() {
-    let mut day = Day20 { algo: HashSet::new(), image: HashMap::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
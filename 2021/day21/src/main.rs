@@ -104,7 +104,12 @@ impl BaseDay for Day21 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day21 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day21 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
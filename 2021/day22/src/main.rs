@@ -244,7 +244,19 @@ impl BaseDay for Day22 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day22 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day22 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(
+    make_day,
+    small,
+    small2,
+    small3,
+    #[ignore = "slow without --release"]
+    input
+);
@@ -27,7 +27,19 @@ fn organize(grid: &Grid<char>, positions: &HashMap<char, Vec<Coord>>) -> i32 {
     }
     println!("Initial state:");
     recurser.print_state(&init_state);
-    return cost_minimizing_dfs(&recurser, &init_state);
+    let solution = cost_minimizing_dfs(&recurser, &init_state);
+    if !solution.is_valid(&recurser) {
+        panic!(
+            "Solver came back with a route that doesn't work: {:?}",
+            solution.states
+        );
+    }
+    println!(
+        "Took {} moves, after expanding {} states",
+        solution.move_costs.len(),
+        solution.states_expanded
+    );
+    return solution.cost;
 }
 
 #[derive(PartialEq, Eq, Clone, Ord, PartialOrd, Debug, Hash)]
@@ -168,6 +180,13 @@ impl SolverBase<State> for Recurser {
         print_grid(&self.grid, &mut print_one);
         println!();
     }
+
+    fn print_path(&self, path: &Vec<State>) {
+        for (idx, st) in path.iter().enumerate() {
+            println!("Step {}:", idx);
+            self.print_state(st);
+        }
+    }
 }
 
 fn calc_moves(
@@ -292,7 +311,18 @@ impl BaseDay for Day23 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day23 { grid: Grid::new(), positions: HashMap::new() });
+}
+
 fn main() {
-    let mut day = Day23 { grid: Grid::new(), positions: HashMap::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(
+    make_day,
+    #[ignore = "slow without --release"]
+    small,
+    #[ignore = "slow without --release"]
+    input
+);
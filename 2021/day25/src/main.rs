@@ -107,7 +107,12 @@ impl BaseDay for Day25 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day25 { vals: Grid::new() });
+}
+
 fn main() {
-    let mut day = Day25 { vals: Grid::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
@@ -0,0 +1,106 @@
+// Each day's main.rs, included as a module so its make_day can be called from here.
+// Their mains are never called, hence the allow(dead_code).
+use common::framework::BaseDay;
+
+#[allow(dead_code)]
+#[path = "../../day01/src/main.rs"]
+mod day01;
+#[allow(dead_code)]
+#[path = "../../day02/src/main.rs"]
+mod day02;
+#[allow(dead_code)]
+#[path = "../../day03/src/main.rs"]
+mod day03;
+#[allow(dead_code)]
+#[path = "../../day04/src/main.rs"]
+mod day04;
+#[allow(dead_code)]
+#[path = "../../day05/src/main.rs"]
+mod day05;
+#[allow(dead_code)]
+#[path = "../../day06/src/main.rs"]
+mod day06;
+#[allow(dead_code)]
+#[path = "../../day07/src/main.rs"]
+mod day07;
+#[allow(dead_code)]
+#[path = "../../day08/src/main.rs"]
+mod day08;
+#[allow(dead_code)]
+#[path = "../../day09/src/main.rs"]
+mod day09;
+#[allow(dead_code)]
+#[path = "../../day10/src/main.rs"]
+mod day10;
+#[allow(dead_code)]
+#[path = "../../day11/src/main.rs"]
+mod day11;
+#[allow(dead_code)]
+#[path = "../../day12/src/main.rs"]
+mod day12;
+#[allow(dead_code)]
+#[path = "../../day13/src/main.rs"]
+mod day13;
+#[allow(dead_code)]
+#[path = "../../day14/src/main.rs"]
+mod day14;
+#[allow(dead_code)]
+#[path = "../../day15/src/main.rs"]
+mod day15;
+#[allow(dead_code)]
+#[path = "../../day16/src/main.rs"]
+mod day16;
+#[allow(dead_code)]
+#[path = "../../day17/src/main.rs"]
+mod day17;
+#[allow(dead_code)]
+#[path = "../../day18/src/main.rs"]
+mod day18;
+#[allow(dead_code)]
+#[path = "../../day19/src/main.rs"]
+mod day19;
+#[allow(dead_code)]
+#[path = "../../day20/src/main.rs"]
+mod day20;
+#[allow(dead_code)]
+#[path = "../../day21/src/main.rs"]
+mod day21;
+#[allow(dead_code)]
+#[path = "../../day22/src/main.rs"]
+mod day22;
+#[allow(dead_code)]
+#[path = "../../day23/src/main.rs"]
+mod day23;
+#[allow(dead_code)]
+#[path = "../../day25/src/main.rs"]
+mod day25;
+
+pub fn make_day(day: u32) -> Option<Box<dyn BaseDay>> {
+    return match day {
+        1 => Some(day01::make_day()),
+        2 => Some(day02::make_day()),
+        3 => Some(day03::make_day()),
+        4 => Some(day04::make_day()),
+        5 => Some(day05::make_day()),
+        6 => Some(day06::make_day()),
+        7 => Some(day07::make_day()),
+        8 => Some(day08::make_day()),
+        9 => Some(day09::make_day()),
+        10 => Some(day10::make_day()),
+        11 => Some(day11::make_day()),
+        12 => Some(day12::make_day()),
+        13 => Some(day13::make_day()),
+        14 => Some(day14::make_day()),
+        15 => Some(day15::make_day()),
+        16 => Some(day16::make_day()),
+        17 => Some(day17::make_day()),
+        18 => Some(day18::make_day()),
+        19 => Some(day19::make_day()),
+        20 => Some(day20::make_day()),
+        21 => Some(day21::make_day()),
+        22 => Some(day22::make_day()),
+        23 => Some(day23::make_day()),
+        25 => Some(day25::make_day()),
+        _ => None,
+    };
+}
@@ -0,0 +1,13 @@
+extern crate common;
+
+mod days;
+
+use std::path::Path;
+
+use common::runner::run_year;
+
+// run 2021 16 small.txt, run 2021 3-7, run 2021 all
+fn main() {
+    let year_dir = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    run_year("2021", year_dir, days::make_day);
+}
@@ -0,0 +1,259 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use crate::grid::{Coord, Grid, GridCoord, InfiniteGrid};
+
+// A cellular automaton over any kind of coord (Coord, Coord3d, CoordN or anything else
+// that's a GridCoord), with its cells in an InfiniteGrid so every cell that hasn't been
+// set has the default value, and it can grow forever. Each step takes a function giving a
+// cell's neighbors, in whatever order the rule expects them (grid::four_neighbors and
+// grid::eight_neighbors work as is, and it can leave out coords that are off the edge of
+// a fixed-size board), and a rule that gets a cell, its value and its neighbors' values
+// and returns its next value.
+//
+// Only cells next to a non-default one get the rule run on them, since anything else is
+// surrounded by defaults. Those all become whatever the rule makes of that, so the
+// background can flip back and forth like in 2021 day20. To find out what that is, the
+// rule gets run on the background coord, as though it and its neighbors were all the
+// default. For an automaton that goes on forever any coord works, but with a neighbors
+// function for a fixed-size board it should be one that isn't on an edge, so it has as
+// many neighbors as the cells it stands in for.
+#[derive(Clone, Debug)]
+pub struct Automaton<C, T>
+where
+    C: GridCoord,
+{
+    grid: InfiniteGrid<T, C>,
+    background: C,
+    generation: usize,
+    // kept around between steps so they don't need to be allocated each time
+    updates: Vec<(C, T)>,
+    candidates: HashSet<C>,
+}
+
+impl<C, T> Automaton<C, T>
+where
+    C: GridCoord,
+    T: Clone + Eq + Hash,
+{
+    pub fn new(default: T, background: C) -> Self {
+        return Automaton {
+            grid: InfiniteGrid::new(default),
+            background,
+            generation: 0,
+            updates: Vec::new(),
+            candidates: HashSet::new(),
+        };
+    }
+
+    pub fn from_cells<I>(cells: I, default: T, background: C) -> Self
+    where
+        I: IntoIterator<Item = (C, T)>,
+    {
+        let mut automaton = Automaton::new(default, background);
+        for (coord, val) in cells {
+            automaton.set(coord, val);
+        }
+        return automaton;
+    }
+
+    pub fn get(&self, coord: &C) -> &T {
+        return self.grid.get(coord);
+    }
+
+    pub fn set(&mut self, coord: C, val: T) {
+        self.grid.set(coord, val);
+    }
+
+    pub fn default(&self) -> &T {
+        return self.grid.default();
+    }
+
+    // How many steps have been run
+    pub fn generation(&self) -> usize {
+        return self.generation;
+    }
+
+    // The cells that aren't the default, in no particular order
+    pub fn cells(&self) -> impl Iterator<Item = (&C, &T)> {
+        return self.grid.iter();
+    }
+
+    // Only counts cells that aren't the default, since there are infinitely many of those
+    pub fn count(&self, val: &T) -> usize {
+        return self.grid.iter().filter(|(_, v)| *v == val).count();
+    }
+
+    // Runs one generation, returning whether anything changed
+    pub fn step<N, R>(&mut self, neighbors: &mut N, rule: &mut R) -> bool
+    where
+        N: FnMut(&C) -> Vec<C>,
+        R: FnMut(&C, &T, &[&T]) -> T,
+    {
+        let around = vec![self.grid.default(); neighbors(&self.background).len()];
+        let new_default = rule(&self.background, self.grid.default(), &around);
+
+        self.candidates.clear();
+        for (coord, _) in self.grid.iter() {
+            self.candidates.insert(*coord);
+            self.candidates.extend(neighbors(coord));
+        }
+
+        let mut changed = new_default != *self.grid.default();
+        self.updates.clear();
+        for coord in &self.candidates {
+            let around = neighbors(coord)
+                .iter()
+                .map(|ngh| self.grid.get(ngh))
+                .collect::<Vec<&T>>();
+            let cur = self.grid.get(coord);
+            let val = rule(coord, cur, &around);
+            changed |= val != *cur;
+            self.updates.push((*coord, val));
+        }
+
+        // everything that wasn't a candidate is surrounded by the old default, so it
+        // becomes the new one
+        self.grid.set_default(new_default);
+        for (coord, val) in self.updates.drain(..) {
+            self.grid.set(coord, val);
+        }
+        self.generation += 1;
+        return changed;
+    }
+
+    pub fn run<N, R>(&mut self, steps: usize, neighbors: &mut N, rule: &mut R)
+    where
+        N: FnMut(&C) -> Vec<C>,
+        R: FnMut(&C, &T, &[&T]) -> T,
+    {
+        for _ in 0..steps {
+            self.step(neighbors, rule);
+        }
+    }
+
+    // Steps until nothing changes any more, and returns the generation where that was
+    pub fn run_until_stable<N, R>(&mut self, neighbors: &mut N, rule: &mut R) -> usize
+    where
+        N: FnMut(&C) -> Vec<C>,
+        R: FnMut(&C, &T, &[&T]) -> T,
+    {
+        while self.step(neighbors, rule) {}
+        return self.generation - 1;
+    }
+
+    // The same for any two states with the same cells, whatever order they're stored in
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.grid.default().hash(&mut hasher);
+        let mut total = hasher.finish();
+        for cell in self.grid.iter() {
+            let mut hasher = DefaultHasher::new();
+            cell.hash(&mut hasher);
+            total = total.wrapping_add(hasher.finish());
+        }
+        return total;
+    }
+
+    // Steps until it gets back to a state it's been in before, and returns the generation
+    // that state was first seen and how long the cycle is. States are only compared by
+    // hash, so in theory two different ones could collide.
+    pub fn run_until_cycle<N, R>(&mut self, neighbors: &mut N, rule: &mut R) -> (usize, usize)
+    where
+        N: FnMut(&C) -> Vec<C>,
+        R: FnMut(&C, &T, &[&T]) -> T,
+    {
+        let mut seen: HashMap<u64, usize> = HashMap::new();
+        loop {
+            if let Some(first) = seen.insert(self.state_hash(), self.generation) {
+                return (first, self.generation - first);
+            }
+            self.step(neighbors, rule);
+        }
+    }
+}
+
+impl<T> Automaton<Coord, T>
+where
+    T: Copy + Eq + Hash,
+{
+    pub fn from_grid(grid: &Grid<T>, default: T, background: Coord) -> Self {
+        let cells = grid.coords.iter().map(|(c, v)| (*c, *v));
+        return Automaton::from_cells(cells, default, background);
+    }
+
+    // Just the cells that aren't the default, with min and max around them
+    pub fn to_grid(&self) -> Grid<T> {
+        let mut grid = Grid::new();
+        for (coord, val) in self.grid.iter() {
+            grid.insert(*coord, *val);
+        }
+        return grid;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::eight_neighbors;
+
+    fn life(_coord: &Coord, alive: &bool, around: &[&bool]) -> bool {
+        let count = around.iter().filter(|a| ***a).count();
+        return count == 3 || (*alive && count == 2);
+    }
+
+    fn row(cells: &[(i32, i32)]) -> Vec<(Coord, bool)> {
+        return cells
+            .iter()
+            .map(|(x, y)| (Coord { x: *x, y: *y }, true))
+            .collect();
+    }
+
+    #[test]
+    fn test_background_flips_from_empty() {
+        // like 2021 day20 when the algorithm starts with a #: all dark turns lit, and
+        // all lit turns dark
+        let mut flip = |_c: &Coord, _v: &bool, around: &[&bool]| -> bool {
+            return around.iter().all(|a| !**a);
+        };
+        let mut automaton = Automaton::new(false, Coord { x: 0, y: 0 });
+        assert!(automaton.step(&mut eight_neighbors, &mut flip));
+        assert!(*automaton.default());
+        assert!(*automaton.get(&Coord { x: 100, y: -100 }));
+        assert_eq!(automaton.cells().count(), 0);
+        assert!(automaton.step(&mut eight_neighbors, &mut flip));
+        assert!(!*automaton.default());
+        assert_eq!(automaton.generation(), 2);
+    }
+
+    #[test]
+    fn test_blinker_cycles() {
+        let blinker = row(&[(0, 1), (1, 1), (2, 1)]);
+        let mut automaton = Automaton::from_cells(blinker, false, Coord { x: 0, y: 0 });
+        automaton.step(&mut eight_neighbors, &mut life);
+        let grid = automaton.to_grid();
+        assert_eq!(
+            (grid.min, grid.max),
+            (Coord { x: 1, y: 0 }, Coord { x: 1, y: 2 })
+        );
+
+        assert_eq!(
+            automaton.run_until_cycle(&mut eight_neighbors, &mut life),
+            (1, 2)
+        );
+    }
+
+    #[test]
+    fn test_block_is_stable() {
+        let block = row(&[(0, 0), (1, 0), (0, 1), (1, 1)]);
+        let mut automaton = Automaton::from_cells(block, false, Coord { x: 0, y: 0 });
+        let before = automaton.state_hash();
+        assert_eq!(
+            automaton.run_until_stable(&mut eight_neighbors, &mut life),
+            0
+        );
+        assert_eq!(automaton.count(&true), 4);
+        assert_eq!(automaton.state_hash(), before);
+    }
+}
@@ -0,0 +1,258 @@
+use std::fs;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::framework::{input_from_bytes, take_flag, BaseDay};
+
+pub const PHASES: [&str; 4] = ["parse", "setup", "pt1", "pt2"];
+
+pub fn timed<F, T>(func: F) -> (T, Duration)
+where
+    F: FnOnce() -> T,
+{
+    let start = Instant::now();
+    let ret = func();
+    return (ret, start.elapsed());
+}
+
+// eg "parse 1.2ms, setup 80ns, pt1 3.4ms, pt2 1.1s"
+pub fn format_times(times: &[Duration]) -> String {
+    return PHASES
+        .iter()
+        .zip(times.iter())
+        .map(|(name, time)| format!("{} {:.1?}", name, time))
+        .collect::<Vec<String>>()
+        .join(", ");
+}
+
+// Runs every phase once on a fresh day, returning the answers and how long each phase took.
+// The input is already in memory so reading the file doesn't count against parse.
+pub fn run_phases(
+    make_day: &dyn Fn() -> Box<dyn BaseDay>,
+    input: &[u8],
+) -> (Vec<String>, Vec<Duration>) {
+    let mut day = make_day();
+    let mut reader = input_from_bytes(input);
+    let (parsed, parse_time) = timed(|| day.try_parse(&mut reader));
+    if let Err(err) = parsed {
+        panic!("{}", err);
+    }
+    let ((), setup_time) = timed(|| day.setup());
+    let (res1, pt1_time) = timed(|| day.pt1());
+    let (res2, pt2_time) = timed(|| day.pt2());
+    return (
+        vec![res1, res2],
+        vec![parse_time, setup_time, pt1_time, pt2_time],
+    );
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct PhaseStats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl PhaseStats {
+    fn from(times: &mut [Duration]) -> PhaseStats {
+        times.sort();
+        return PhaseStats {
+            min: times[0],
+            median: times[times.len() / 2],
+            max: times[times.len() - 1],
+        };
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BenchResult {
+    pub runs: usize,
+    pub warmup: usize,
+    pub answers: Vec<String>,
+    // one per phase, in the same order as PHASES
+    pub stats: Vec<PhaseStats>,
+}
+
+impl BenchResult {
+    pub fn print(&self) {
+        println!("Bench: {} runs after {} warmup", self.runs, self.warmup);
+        for (name, stats) in PHASES.iter().zip(self.stats.iter()) {
+            println!(
+                "  {:5}  min {:>10.1?}  median {:>10.1?}  max {:>10.1?}",
+                name, stats.min, stats.median, stats.max
+            );
+        }
+    }
+
+    // times are in nanoseconds
+    pub fn to_json(&self, fname: &str) -> String {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let answers = self
+            .answers
+            .iter()
+            .map(|a| json_str(a))
+            .collect::<Vec<String>>();
+        let phases = PHASES
+            .iter()
+            .zip(self.stats.iter())
+            .map(|(name, stats)| {
+                format!(
+                    "    {}: {{\"min_ns\": {}, \"median_ns\": {}, \"max_ns\": {}}}",
+                    json_str(name),
+                    stats.min.as_nanos(),
+                    stats.median.as_nanos(),
+                    stats.max.as_nanos()
+                )
+            })
+            .collect::<Vec<String>>();
+        return format!(
+            "{{\n  \"input\": {},\n  \"timestamp\": {},\n  \"runs\": {},\n  \"warmup\": {},\n  \"answers\": [{}],\n  \"phases\": {{\n{}\n  }}\n}}\n",
+            json_str(fname),
+            secs,
+            self.runs,
+            self.warmup,
+            answers.join(", "),
+            phases.join(",\n")
+        );
+    }
+}
+
+fn json_str(val: &str) -> String {
+    let mut ret = String::from("\"");
+    for c in val.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            c if (c as u32) < 0x20 => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    return ret;
+}
+
+// Throws away the first warmup runs, then keeps the times of the next runs
+pub fn bench(
+    make_day: &dyn Fn() -> Box<dyn BaseDay>,
+    input: &[u8],
+    runs: usize,
+    warmup: usize,
+) -> BenchResult {
+    if runs == 0 {
+        panic!("Need at least one run to bench");
+    }
+    for _ in 0..warmup {
+        run_phases(make_day, input);
+    }
+    let mut answers = Vec::new();
+    let mut all_times: Vec<Vec<Duration>> = vec![Vec::new(); PHASES.len()];
+    for _ in 0..runs {
+        let (run_answers, times) = run_phases(make_day, input);
+        for (idx, time) in times.into_iter().enumerate() {
+            all_times[idx].push(time);
+        }
+        answers = run_answers;
+    }
+    return BenchResult {
+        runs,
+        warmup,
+        answers,
+        stats: all_times
+            .iter_mut()
+            .map(|times| PhaseStats::from(times))
+            .collect(),
+    };
+}
+
+// Benchmarks the day on a file, prints the stats, and writes them to fname.bench.json
+pub fn bench_file(
+    make_day: &dyn Fn() -> Box<dyn BaseDay>,
+    fname: &str,
+    runs: usize,
+    warmup: usize,
+) -> BenchResult {
+    let input = match fs::read(fname) {
+        Ok(input) => input,
+        Err(_) => panic!("Bad file: {}", fname),
+    };
+    let result = bench(make_day, &input, runs, warmup);
+    result.print();
+    let summary = fname.to_owned() + ".bench.json";
+    fs::write(&summary, result.to_json(fname)).unwrap();
+    println!("Wrote {}", summary);
+    return result;
+}
+
+// --bench N and --warmup M (which defaults to 1), taken out of args
+pub fn take_bench_flags(args: &mut Vec<String>) -> Option<(usize, usize)> {
+    let warmup = match take_flag(args, "--warmup") {
+        Some(val) => val.parse::<usize>().expect("Bad --warmup"),
+        None => 1,
+    };
+    let runs = take_flag(args, "--bench")?;
+    return Some((runs.parse::<usize>().expect("Bad --bench"), warmup));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_str() {
+        assert_eq!(json_str("abc"), r#""abc""#);
+        assert_eq!(json_str(r#"say "hi"\n"#), r#""say \"hi\"\\n""#);
+        assert_eq!(json_str("a\nb"), r#""a\nb""#);
+        assert_eq!(json_str("\t\u{1f}\u{0}"), r#""\u0009\u001f\u0000""#);
+        // only control characters get escaped
+        assert_eq!(json_str("é/\u{7f}"), "\"é/\u{7f}\"");
+    }
+
+    #[test]
+    fn test_phase_stats() {
+        let mut times = [5, 1, 9, 3].map(Duration::from_nanos);
+        let stats = PhaseStats::from(&mut times);
+        assert_eq!(stats.min, Duration::from_nanos(1));
+        assert_eq!(stats.median, Duration::from_nanos(5));
+        assert_eq!(stats.max, Duration::from_nanos(9));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let stats = |ns: u64| PhaseStats {
+            min: Duration::from_nanos(ns),
+            median: Duration::from_nanos(ns * 2),
+            max: Duration::from_nanos(ns * 3),
+        };
+        let result = BenchResult {
+            runs: 3,
+            warmup: 1,
+            answers: vec!["42".to_string(), "#.#/\"x\"".to_string()],
+            stats: vec![stats(1), stats(10), stats(100), stats(1000)],
+        };
+        let json = result.to_json("day01/in\\put.txt");
+        let mut lines = json.lines().collect::<Vec<&str>>();
+        // the timestamp is whenever the test runs
+        let timestamp = lines.remove(2);
+        assert!(timestamp.starts_with("  \"timestamp\": "));
+        assert!(timestamp.trim_end_matches(',')[15..].parse::<u64>().is_ok());
+        let expected = [
+            "{",
+            r#"  "input": "day01/in\\put.txt","#,
+            r#"  "runs": 3,"#,
+            r#"  "warmup": 1,"#,
+            r##"  "answers": ["42", "#.#/\"x\""],"##,
+            r#"  "phases": {"#,
+            r#"    "parse": {"min_ns": 1, "median_ns": 2, "max_ns": 3},"#,
+            r#"    "setup": {"min_ns": 10, "median_ns": 20, "max_ns": 30},"#,
+            r#"    "pt1": {"min_ns": 100, "median_ns": 200, "max_ns": 300},"#,
+            r#"    "pt2": {"min_ns": 1000, "median_ns": 2000, "max_ns": 3000}"#,
+            "  }",
+            "}",
+        ];
+        assert_eq!(lines, expected);
+        assert!(json.ends_with("}\n"));
+    }
+}
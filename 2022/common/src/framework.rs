@@ -2,36 +2,95 @@ use std::boxed::Box;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
+use std::io::{self, BufRead, BufReader, Cursor, Read};
 use std::str::FromStr;
 
 use lazy_regex::{regex, Captures, Lazy, Regex};
 
+use crate::bench::{bench_file, run_phases, take_bench_flags};
+use crate::dense_grid::DenseGrid;
 use crate::grid::{Coord, Grid};
+use crate::parse_error::ParseError;
+use crate::runner::{find_inputs, print_summary, run_file};
 
-pub type InputReader = BufReader<Box<dyn Read>>;
+// Reads the input, keeping count of the lines so parse errors can say where they are
+pub struct InputReader {
+    inner: BufReader<Box<dyn Read>>,
+    lines_read: usize,
+}
+
+impl InputReader {
+    pub fn new(inner: Box<dyn Read>) -> InputReader {
+        return InputReader { inner: BufReader::new(inner), lines_read: 0 };
+    }
+
+    // How many lines have been read so far, so the next one is line_num() + 1
+    pub fn line_num(&self) -> usize {
+        return self.lines_read;
+    }
+}
+
+fn count_newlines(buf: &[u8]) -> usize {
+    return buf.iter().filter(|b| **b == b'\n').count();
+}
+
+impl Read for InputReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.lines_read += count_newlines(&buf[..len]);
+        return Ok(len);
+    }
+}
 
+impl BufRead for InputReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        return self.inner.fill_buf();
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.lines_read += count_newlines(&self.inner.buffer()[..amt]);
+        self.inner.consume(amt);
+    }
+}
+
+// parse panics on bad input. A day can also implement try_parse, which says what was
+// wrong with it and where, and then have parse just unwrap that. The runners call
+// try_parse and print the error.
 pub trait BaseDay {
     fn parse(&mut self, input: &mut InputReader);
+    fn try_parse(&mut self, input: &mut InputReader) -> Result<(), ParseError> {
+        self.parse(input);
+        return Ok(());
+    }
     fn setup(&mut self) {}
     fn pt1(&mut self) -> String;
     fn pt2(&mut self) -> String;
 }
 
 pub fn parse_regexps<F, T>(line: &str, rexes: &mut Vec<(&Lazy<Regex>, F)>) -> T
+where
+    F: FnMut(Captures) -> T,
+{
+    return try_parse_regexps(line, rexes).unwrap_or_else(|err| panic!("{}", err));
+}
+
+pub fn try_parse_regexps<F, T>(
+    line: &str,
+    rexes: &mut Vec<(&Lazy<Regex>, F)>,
+) -> Result<T, ParseError>
 where
     F: FnMut(Captures) -> T,
 {
     for (rex, func) in rexes {
         match rex.captures(line) {
             Some(c) => {
-                return func(c);
+                return Ok(func(c));
             }
             None => {}
         }
     }
 
-    panic!("Can't parse line: {}", line);
+    return Err(ParseError::new("Can't parse line").at_line(0, line));
 }
 
 pub fn parse_regexp<F, T>(line: &str, rex: &Lazy<Regex>, func: &mut F) -> T
@@ -41,65 +100,256 @@ where
     return parse_regexps(line, &mut vec![(rex, func)]);
 }
 
+pub fn try_parse_regexp<F, T>(line: &str, rex: &Lazy<Regex>, func: &mut F) -> Result<T, ParseError>
+where
+    F: FnMut(Captures) -> T,
+{
+    return try_parse_regexps(line, &mut vec![(rex, func)]);
+}
+
 pub fn parse_vals<T>(line: &str) -> Vec<T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    return try_parse_vals(line).unwrap_or_else(|err| panic!("{}", err));
+}
+
+// The error has the column of the value that didn't parse
+pub fn try_parse_vals<T>(line: &str) -> Result<Vec<T>, ParseError>
 where
     T: FromStr,
     <T as FromStr>::Err: Debug,
 {
     let sep = regex!(r#"\s*,\s*"#);
-    let ws = sep.split(line.trim());
-    return ws.map(|n| n.parse::<T>().unwrap()).collect::<Vec<T>>();
+    let offset = line.len() - line.trim_start().len();
+    let trimmed = line.trim();
+
+    let mut bounds = sep
+        .find_iter(trimmed)
+        .map(|m| (m.start(), m.end()))
+        .collect::<Vec<(usize, usize)>>();
+    bounds.push((trimmed.len(), trimmed.len()));
+    let mut vals = Vec::new();
+    let mut start = 0;
+    for (end, next) in bounds {
+        let word = &trimmed[start..end];
+        match word.parse::<T>() {
+            Ok(val) => vals.push(val),
+            Err(err) => {
+                // columns are in chars, not bytes
+                let column = line[..offset + start].chars().count() + 1;
+                return Err(
+                    ParseError::new(&format!("Can't parse {:?}: {:?}", word, err))
+                        .at_column(column)
+                        .at_line(0, line),
+                );
+            }
+        }
+        start = next;
+    }
+    return Ok(vals);
 }
 
 pub fn parse_lines<F, T>(input: &mut InputReader, parse_line: &mut F) -> Vec<T>
 where
     F: FnMut(String) -> T,
 {
+    return try_parse_lines(input, &mut |line| Ok(parse_line(line))).unwrap();
+}
+
+// Reads lines up to a blank one (which it consumes). Any error from parse_line gets the
+// line number and text filled in.
+pub fn try_parse_lines<F, T>(
+    input: &mut InputReader,
+    parse_line: &mut F,
+) -> Result<Vec<T>, ParseError>
+where
+    F: FnMut(String) -> Result<T, ParseError>,
+{
+    let start = input.line_num();
     let mut vals = Vec::new();
-    for line in input.lines() {
+    for (idx, line) in input.lines().enumerate() {
         let line = line.unwrap();
         if line.len() == 0 {
             break;
         }
-        vals.push(parse_line(line));
+        let text = line.clone();
+        vals.push(parse_line(line).map_err(|err| err.at_line(start + idx + 1, &text))?);
     }
-    return vals;
+    return Ok(vals);
+}
+
+// For types whose FromStr gives a ParseError, like the ones scan::line_format! makes
+pub fn parse_lines_as<T>(input: &mut InputReader) -> Vec<T>
+where
+    T: FromStr<Err = ParseError>,
+{
+    return try_parse_lines_as(input).unwrap_or_else(|err| panic!("{}", err));
+}
+
+pub fn try_parse_lines_as<T>(input: &mut InputReader) -> Result<Vec<T>, ParseError>
+where
+    T: FromStr<Err = ParseError>,
+{
+    return try_parse_lines(input, &mut |line| line.parse::<T>());
 }
 
 // Potentially reads multiple lines, but accumulates into a single
 // vec of numbers. Stops if it sees a blank line (which it consumes).
 pub fn parse_numbers(input: &mut InputReader) -> Vec<i32> {
+    return try_parse_numbers(input).unwrap_or_else(|err| panic!("{}", err));
+}
+
+pub fn try_parse_numbers(input: &mut InputReader) -> Result<Vec<i32>, ParseError> {
+    let start = input.line_num();
     let mut vals = Vec::new();
-    for line in input.lines() {
+    for (idx, line) in input.lines().enumerate() {
         let line = line.unwrap();
         if line.is_empty() {
-            return vals;
+            return Ok(vals);
         }
-        vals.extend(parse_vals::<i32>(&line));
+        vals.extend(
+            try_parse_vals::<i32>(&line).map_err(|err| err.at_line(start + idx + 1, &line))?,
+        );
     }
-    return vals;
+    return Ok(vals);
+}
+
+// How read_records splits up the input. Blank lines are skipped except with Blank.
+pub enum RecordSep<'a> {
+    // records have blank lines between them
+    Blank,
+    // each record starts with a line matching this, like "--- scanner 3 ---" or
+    // "Tile 2311:", which is its header
+    Header(&'a Regex),
+    // lines matching this go between records, and aren't part of either one
+    Delimiter(&'a Regex),
+    // every n lines is a record, eg 2 for pairs of packets
+    Count(usize),
+}
+
+pub struct Record {
+    pub header: Option<String>,
+    pub lines: Vec<String>,
+    header_line: usize,
+    line_nums: Vec<usize>,
+}
+
+impl Record {
+    fn new(header: Option<String>, header_line: usize) -> Record {
+        return Record {
+            header,
+            lines: Vec::new(),
+            header_line,
+            line_nums: Vec::new(),
+        };
+    }
+
+    // A line number in err counts from the start of the record's lines, and if it doesn't
+    // have one, the error goes on the header (or the first line if there isn't a header)
+    fn locate(&self, err: ParseError) -> ParseError {
+        let (line, text) = match (err.line, &self.header) {
+            (0, Some(header)) => (self.header_line, header.as_str()),
+            (0, None) => (self.line_nums[0], self.lines[0].as_str()),
+            (n, _) => match self.line_nums.get(n - 1) {
+                Some(line) => (*line, self.lines[n - 1].as_str()),
+                None => (0, ""),
+            },
+        };
+        return ParseError { line: 0, ..err }.at_line(line, text);
+    }
+}
+
+// Reads the rest of the input, split up into records (leaving out any empty ones)
+pub fn read_records(input: &mut InputReader, sep: &RecordSep) -> Vec<Record> {
+    let start = input.line_num();
+    let mut records = Vec::new();
+    let mut cur = Record::new(None, 0);
+    for (idx, line) in input.lines().enumerate() {
+        let line = line.unwrap();
+        let line_num = start + idx + 1;
+        let next = match sep {
+            RecordSep::Blank if line.is_empty() => Some(Record::new(None, 0)),
+            RecordSep::Header(rex) if rex.is_match(&line) => {
+                Some(Record::new(Some(line.clone()), line_num))
+            }
+            RecordSep::Delimiter(rex) if rex.is_match(&line) => Some(Record::new(None, 0)),
+            _ if line.is_empty() => continue,
+            _ => None,
+        };
+        match next {
+            Some(next) => {
+                let done = std::mem::replace(&mut cur, next);
+                if done.header.is_some() || !done.lines.is_empty() {
+                    records.push(done);
+                }
+            }
+            None => {
+                cur.lines.push(line);
+                cur.line_nums.push(line_num);
+                if let RecordSep::Count(n) = sep {
+                    if cur.lines.len() == *n {
+                        records.push(std::mem::replace(&mut cur, Record::new(None, 0)));
+                    }
+                }
+            }
+        }
+    }
+    if cur.header.is_some() || !cur.lines.is_empty() {
+        records.push(cur);
+    }
+    return records;
 }
 
 pub fn parse_records<F, T>(input: &mut InputReader, parse_record: &mut F) -> Vec<T>
 where
     F: FnMut(&Vec<String>) -> T,
+{
+    return parse_records_by(input, &RecordSep::Blank, &mut |record: &Record| {
+        parse_record(&record.lines)
+    });
+}
+
+pub fn try_parse_records<F, T>(
+    input: &mut InputReader,
+    parse_record: &mut F,
+) -> Result<Vec<T>, ParseError>
+where
+    F: FnMut(&Vec<String>) -> Result<T, ParseError>,
+{
+    return try_parse_records_by(input, &RecordSep::Blank, &mut |record: &Record| {
+        parse_record(&record.lines)
+    });
+}
+
+pub fn parse_records_by<F, T>(
+    input: &mut InputReader,
+    sep: &RecordSep,
+    parse_record: &mut F,
+) -> Vec<T>
+where
+    F: FnMut(&Record) -> T,
+{
+    return try_parse_records_by(input, sep, &mut |record: &Record| Ok(parse_record(record)))
+        .unwrap();
+}
+
+// A line number in an error from parse_record counts from the start of the record's
+// lines, and without one the error goes on the record's header or first line
+pub fn try_parse_records_by<F, T>(
+    input: &mut InputReader,
+    sep: &RecordSep,
+    parse_record: &mut F,
+) -> Result<Vec<T>, ParseError>
+where
+    F: FnMut(&Record) -> Result<T, ParseError>,
 {
     let mut vals = Vec::new();
-    let mut cur = Vec::new();
-    for line in input.lines() {
-        let line = line.unwrap();
-        if line.is_empty() {
-            // TODO: alternate record separators?
-            vals.push(parse_record(&cur));
-            cur.clear();
-        } else {
-            cur.push(line);
-        }
+    for record in read_records(input, sep) {
+        vals.push(parse_record(&record).map_err(|err| record.locate(err))?);
     }
-    if cur.len() > 0 {
-        vals.push(parse_record(&cur));
-    }
-    return vals;
+    return Ok(vals);
 }
 
 pub fn parse_grid<F, T>(input: &mut InputReader, parse_coord: &mut F) -> Grid<T>
@@ -109,6 +359,16 @@ where
     return parse_grid_record(input, false, parse_coord);
 }
 
+pub fn try_parse_grid<F, T>(
+    input: &mut InputReader,
+    parse_coord: &mut F,
+) -> Result<Grid<T>, ParseError>
+where
+    F: FnMut(char, &Coord) -> Result<Option<T>, ParseError>,
+{
+    return try_parse_grid_record(input, false, parse_coord);
+}
+
 pub fn parse_grid_record<F, T>(
     input: &mut InputReader,
     end_on_blank: bool,
@@ -117,6 +377,22 @@ pub fn parse_grid_record<F, T>(
 where
     F: FnMut(char, &Coord) -> Option<T>,
 {
+    return try_parse_grid_record(input, end_on_blank, &mut |ch, coord| {
+        Ok(parse_coord(ch, coord))
+    })
+    .unwrap();
+}
+
+// Errors from parse_coord get the line and column of the character filled in
+pub fn try_parse_grid_record<F, T>(
+    input: &mut InputReader,
+    end_on_blank: bool,
+    parse_coord: &mut F,
+) -> Result<Grid<T>, ParseError>
+where
+    F: FnMut(char, &Coord) -> Result<Option<T>, ParseError>,
+{
+    let start = input.line_num();
     let mut coords: HashMap<Coord, T> = HashMap::new();
     let mut max_x = 0;
     let mut max_y = 0;
@@ -127,15 +403,18 @@ where
             break;
         }
 
+        let line_num = start + y + 1;
         let y = y as i32;
         for (x, ch) in line.chars().enumerate() {
+            let column = x + 1;
             let x = x as i32;
             let coord = Coord { x: x, y: y };
             match parse_coord(ch, &coord) {
-                Some(val) => {
+                Ok(Some(val)) => {
                     coords.insert(coord, val);
                 }
-                None => {}
+                Ok(None) => {}
+                Err(err) => return Err(err.at_column(column).at_line(line_num, &line)),
             }
             if x > max_x {
                 max_x = x;
@@ -143,41 +422,419 @@ where
         }
         max_y = y;
     }
-    return Grid::<T> {
+    return Ok(Grid::<T> {
         coords: coords,
         min: Coord { x: 0, y: 0 },
         max: Coord { x: max_x, y: max_y },
-    };
+    });
 }
 
-fn load_expected(fname: &str) -> Vec<String> {
-    let mut expected = Vec::new();
-    match File::open(fname.to_owned() + ".expected") {
-        Ok(f) => expected.extend(BufReader::new(f).lines().map(|line| line.unwrap())),
-        Err(_) => {}
+pub fn parse_dense_grid<F, T>(input: &mut InputReader, parse_coord: &mut F) -> DenseGrid<T>
+where
+    F: FnMut(char, &Coord) -> T,
+{
+    return try_parse_dense_grid(input, &mut |ch, coord| Ok(parse_coord(ch, coord)))
+        .unwrap_or_else(|err| panic!("{}", err));
+}
+
+pub fn try_parse_dense_grid<F, T>(
+    input: &mut InputReader,
+    parse_coord: &mut F,
+) -> Result<DenseGrid<T>, ParseError>
+where
+    F: FnMut(char, &Coord) -> Result<T, ParseError>,
+{
+    return try_parse_dense_grid_record(input, false, parse_coord);
+}
+
+// Like try_parse_grid_record, but every line has to be the same width
+pub fn try_parse_dense_grid_record<F, T>(
+    input: &mut InputReader,
+    end_on_blank: bool,
+    parse_coord: &mut F,
+) -> Result<DenseGrid<T>, ParseError>
+where
+    F: FnMut(char, &Coord) -> Result<T, ParseError>,
+{
+    let start = input.line_num();
+    let mut rows: Vec<Vec<T>> = Vec::new();
+    for (y, line) in input.lines().enumerate() {
+        let line = line.unwrap();
+
+        if line.len() == 0 && end_on_blank {
+            break;
+        }
+
+        let line_num = start + y + 1;
+        let mut row = Vec::new();
+        for (x, ch) in line.chars().enumerate() {
+            let coord = Coord { x: x as i32, y: y as i32 };
+            match parse_coord(ch, &coord) {
+                Ok(val) => row.push(val),
+                Err(err) => return Err(err.at_column(x + 1).at_line(line_num, &line)),
+            }
+        }
+        if let Some(first) = rows.first() {
+            if row.len() != first.len() {
+                let msg = format!(
+                    "Line is {} wide, but the first one is {}",
+                    row.len(),
+                    first.len()
+                );
+                return Err(ParseError::new(&msg).at_line(line_num, &line));
+            }
+        }
+        rows.push(row);
+    }
+    return Ok(DenseGrid::from_rows(rows));
+}
+
+// Reads input made of differently formatted sections one after another, like 2022 day05's
+// drawing of the stacks and then the moves, with a blank line after each one except
+// records, which go to the end. Errors say which section they're in:
+//
+//     let mut sections = input.sections();
+//     self.points = sections.lines("points", &mut parse_point)?;
+//     self.folds = sections.lines("folds", &mut parse_fold)?;
+pub struct Sections<'a> {
+    input: &'a mut InputReader,
+    count: usize,
+}
+
+impl InputReader {
+    pub fn sections(&mut self) -> Sections<'_> {
+        return Sections { input: self, count: 0 };
+    }
+}
+
+impl<'a> Sections<'a> {
+    fn label<T>(&mut self, name: &str, parsed: Result<T, ParseError>) -> Result<T, ParseError> {
+        self.count += 1;
+        let section = format!("section {} ({})", self.count, name);
+        return parsed.map_err(|err| err.in_section(&section));
+    }
+
+    pub fn lines<F, T>(&mut self, name: &str, parse_line: &mut F) -> Result<Vec<T>, ParseError>
+    where
+        F: FnMut(String) -> Result<T, ParseError>,
+    {
+        let parsed = try_parse_lines(self.input, parse_line);
+        return self.label(name, parsed);
+    }
+
+    pub fn lines_as<T>(&mut self, name: &str) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr<Err = ParseError>,
+    {
+        let parsed = try_parse_lines_as(self.input);
+        return self.label(name, parsed);
+    }
+    pub fn numbers(&mut self, name: &str) -> Result<Vec<i32>, ParseError> {
+        let parsed = try_parse_numbers(self.input);
+        return self.label(name, parsed);
+    }
+
+    pub fn grid<F, T>(&mut self, name: &str, parse_coord: &mut F) -> Result<Grid<T>, ParseError>
+    where
+        F: FnMut(char, &Coord) -> Result<Option<T>, ParseError>,
+    {
+        let parsed = try_parse_grid_record(self.input, true, parse_coord);
+        return self.label(name, parsed);
+    }
+
+    pub fn dense_grid<F, T>(
+        &mut self,
+        name: &str,
+        parse_coord: &mut F,
+    ) -> Result<DenseGrid<T>, ParseError>
+    where
+        F: FnMut(char, &Coord) -> Result<T, ParseError>,
+    {
+        let parsed = try_parse_dense_grid_record(self.input, true, parse_coord);
+        return self.label(name, parsed);
+    }
+
+    pub fn records<F, T>(
+        &mut self,
+        name: &str,
+        sep: &RecordSep,
+        parse_record: &mut F,
+    ) -> Result<Vec<T>, ParseError>
+    where
+        F: FnMut(&Record) -> Result<T, ParseError>,
+    {
+        let parsed = try_parse_records_by(self.input, sep, parse_record);
+        return self.label(name, parsed);
+    }
+}
+
+// "-" means stdin
+pub fn open_input(fname: &str) -> InputReader {
+    if fname == "-" {
+        return InputReader::new(Box::new(io::stdin()));
+    }
+    return match File::open(fname) {
+        Ok(f) => InputReader::new(Box::new(f)),
+        Err(_) => panic!("Bad file: {}", fname),
     };
+}
+
+pub fn input_from_bytes(input: &[u8]) -> InputReader {
+    return InputReader::new(Box::new(Cursor::new(input.to_vec())));
+}
+
+// Runs both parts on input and returns the answers without printing or checking them,
+// eg for an example pasted in from the puzzle text
+pub fn run_day_on_str(make_day: fn() -> Box<dyn BaseDay>, input: &str) -> (String, String) {
+    let (answers, _) = run_phases(&make_day, input.as_bytes());
+    return (answers[0].clone(), answers[1].clone());
+}
+
+// Stdin can only be read once, so it can't be benchmarked, and it doesn't have a
+// .expected to write to
+pub fn check_stdin_flags(fname: &str, bench: bool, write: bool) {
+    if fname == "-" && bench {
+        panic!("Can't use --bench when reading from stdin");
+    }
+    if fname == "-" && write {
+        panic!("Can't use --write-expected when reading from stdin");
+    }
+}
+
+// The answers in fname.expected, one per line, or None if there isn't one
+pub fn read_expected(fname: &str) -> Option<Vec<String>> {
+    let f = File::open(fname.to_owned() + ".expected").ok()?;
+    let mut expected: Vec<String> = BufReader::new(f)
+        .lines()
+        .map(|line| line.unwrap())
+        .collect();
     while expected.len() < 2 {
         expected.push("".to_string());
     }
-    return expected;
+    return Some(expected);
 }
 
-pub fn run_day(day: &mut dyn BaseDay) {
-    let args: Vec<String> = std::env::args().collect();
+// Removes "--name value" from args if it's there, and returns the value
+pub fn take_flag(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let idx = args.iter().position(|a| a == name)?;
+    if idx + 1 >= args.len() {
+        panic!("Missing value for {}", name);
+    }
+    let val = args.remove(idx + 1);
+    args.remove(idx);
+    return Some(val);
+}
 
-    let mut input: InputReader = match File::open(&args[1]) {
-        Ok(f) => BufReader::new(Box::new(f)),
-        Err(_) => panic!("Bad file: {}", &args[1]),
-    };
-    day.parse(&mut input);
-    day.setup();
+// Removes "--name" from args, returning whether it was there
+pub fn take_switch(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|a| a == name) {
+        Some(idx) => {
+            args.remove(idx);
+            return true;
+        }
+        None => return false,
+    }
+}
+
+// Runs the day on the file given as the first argument and checks the answers against
+// file.expected, carrying on to part 2 even if part 1 is wrong (or panics), and exiting
+// with an error at the end if either was. The argument can also be a directory or a glob,
+// to run every input there that has a .expected (see runner::find_inputs) and show a table
+// of how they all did, or "-" to read the input from stdin. --write-expected fills in any
+// answers a .expected doesn't have yet, and --bench N then runs everything N more times on
+// fresh days (see bench::bench_file).
+pub fn run_day(make_day: fn() -> Box<dyn BaseDay>) {
+    let mut args: Vec<String> = std::env::args().collect();
+    let bench_flags = take_bench_flags(&mut args);
+    let write = take_switch(&mut args, "--write-expected");
 
-    let expected = load_expected(&args[1]);
+    check_stdin_flags(&args[1], bench_flags.is_some(), write);
+
+    let fnames = find_inputs(&args[1]);
+    if fnames.is_empty() {
+        panic!("No inputs with .expected files in {}", &args[1]);
+    }
+    let mut reports = Vec::new();
+    for fname in &fnames {
+        let report = run_file(None, &mut *make_day(), fname, write);
+        if let (Some((runs, warmup)), true) = (bench_flags, report.finished()) {
+            bench_file(&make_day, fname, runs, warmup);
+        }
+        reports.push(report);
+    }
+
+    if fnames.len() > 1 || fnames[0] != args[1] {
+        print_summary(&reports);
+    }
+    if !reports.iter().all(|r| r.is_ok()) {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vals() {
+        assert_eq!(try_parse_vals::<i32>(" 1, -2,3 ").unwrap(), vec![1, -2, 3]);
+        // the é is two bytes, but one column
+        let err = try_parse_vals::<char>(" é, xy").unwrap_err();
+        assert_eq!(err.column, 5);
+        assert_eq!(err.text, " é, xy");
+    }
+
+    fn records(text: &str, sep: &RecordSep) -> Vec<(Option<String>, Vec<String>)> {
+        let mut input = input_from_bytes(text.as_bytes());
+        return read_records(&mut input, sep)
+            .into_iter()
+            .map(|record| (record.header, record.lines))
+            .collect();
+    }
 
-    let res1 = day.pt1();
-    println!("Result 1: {}", res1);
-    assert_eq!(res1, expected[0]);
-    let res2 = day.pt2();
-    println!("Result 2: {}", res2);
-    assert_eq!(res2, expected[1]);
+    // Parses text with a record parser that fails on the given record, at the given line
+    // of it (0 for the record as a whole)
+    fn fail_on(text: &str, sep: &RecordSep, record: usize, line: usize) -> ParseError {
+        let mut input = input_from_bytes(text.as_bytes());
+        let mut count = 0;
+        return try_parse_records_by(&mut input, sep, &mut |_: &Record| {
+            count += 1;
+            if count == record {
+                return Err(ParseError::new("Bad record").at_line(line, ""));
+            }
+            return Ok(());
+        })
+        .unwrap_err();
+    }
+
+    fn strs(lines: &[&str]) -> Vec<String> {
+        return lines.iter().map(|line| line.to_string()).collect();
+    }
+
+    #[test]
+    fn test_records_by_header() {
+        let sep = RecordSep::Header(regex!(r"^--- \w+ ---$"));
+        let text = "--- a ---\n1\n2\n\n--- b ---\n3\n";
+        assert_eq!(
+            records(text, &sep),
+            vec![
+                (Some("--- a ---".to_string()), strs(&["1", "2"])),
+                (Some("--- b ---".to_string()), strs(&["3"])),
+            ]
+        );
+
+        let err = fail_on(text, &sep, 2, 0);
+        assert_eq!((err.line, err.text.as_str()), (5, "--- b ---"));
+        let err = fail_on(text, &sep, 2, 1);
+        assert_eq!((err.line, err.text.as_str()), (6, "3"));
+    }
+
+    #[test]
+    fn test_records_by_delimiter() {
+        let sep = RecordSep::Delimiter(regex!(r"^=+$"));
+        let text = "==\n1\n2\n===\n\n3\n";
+        assert_eq!(
+            records(text, &sep),
+            vec![(None, strs(&["1", "2"])), (None, strs(&["3"]))]
+        );
+
+        // without a header, the error goes on the record's first line
+        let err = fail_on(text, &sep, 2, 0);
+        assert_eq!((err.line, err.text.as_str()), (6, "3"));
+        let err = fail_on(text, &sep, 1, 2);
+        assert_eq!((err.line, err.text.as_str()), (3, "2"));
+    }
+
+    #[test]
+    fn test_records_by_count() {
+        let sep = RecordSep::Count(2);
+        let text = "a\nb\n\nc\nd\ne\n";
+        assert_eq!(
+            records(text, &sep),
+            vec![
+                (None, strs(&["a", "b"])),
+                (None, strs(&["c", "d"])),
+                (None, strs(&["e"]))
+            ]
+        );
+
+        let err = fail_on(text, &sep, 2, 2);
+        assert_eq!((err.line, err.text.as_str()), (5, "d"));
+        let err = fail_on(text, &sep, 3, 0);
+        assert_eq!((err.line, err.text.as_str()), (6, "e"));
+    }
+
+    #[test]
+    fn test_records_by_blank() {
+        let text = "a\nb\n\n\nc\n";
+        assert_eq!(
+            records(text, &RecordSep::Blank),
+            vec![(None, strs(&["a", "b"])), (None, strs(&["c"]))]
+        );
+        let err = fail_on(text, &RecordSep::Blank, 2, 1);
+        assert_eq!((err.line, err.text.as_str()), (5, "c"));
+    }
+
+    fn parse_move(line: String) -> Result<i32, ParseError> {
+        return match line.strip_prefix("move ") {
+            Some(num) => num
+                .parse()
+                .map_err(|_| ParseError::new("Bad count").at_column(6)),
+            None => Err(ParseError::new("Not a move")),
+        };
+    }
+
+    #[test]
+    fn test_sections() {
+        let text = "1,2\n3\n\n.#\n#.\n\n#..\n..#\n\nmove 1\nmove 2\n\nx\ny\n\nz\n";
+        let mut input = input_from_bytes(text.as_bytes());
+        let mut sections = input.sections();
+        assert_eq!(sections.numbers("nums").unwrap(), vec![1, 2, 3]);
+
+        let grid = sections
+            .grid("sparse", &mut |ch, _| {
+                Ok(if ch == '#' { Some(ch) } else { None })
+            })
+            .unwrap();
+        assert_eq!(grid.coords.len(), 2);
+        assert_eq!(grid.coords.get(&Coord { x: 1, y: 0 }), Some(&'#'));
+
+        let dense = sections
+            .dense_grid("dense", &mut |ch, _| Ok(ch == '#'))
+            .unwrap();
+        assert_eq!((dense.width(), dense.height()), (3, 2));
+        assert!(dense[Coord { x: 2, y: 1 }]);
+
+        assert_eq!(
+            sections.lines("moves", &mut parse_move).unwrap(),
+            vec![1, 2]
+        );
+
+        let records = sections
+            .records("rest", &RecordSep::Blank, &mut |record: &Record| {
+                Ok(record.lines.join(""))
+            })
+            .unwrap();
+        assert_eq!(records, vec!["xy", "z"]);
+    }
+
+    #[test]
+    fn test_section_errors() {
+        let text = "1\n\nmove 1\nmove x\n";
+        let mut input = input_from_bytes(text.as_bytes());
+        let mut sections = input.sections();
+        sections.numbers("nums").unwrap();
+        let err = sections.lines("moves", &mut parse_move).unwrap_err();
+        assert_eq!(err.section, "section 2 (moves)");
+        assert_eq!((err.line, err.column, err.text.as_str()), (4, 6, "move x"));
+
+        let text = "..\n.\n";
+        let mut input = input_from_bytes(text.as_bytes());
+        let err = input
+            .sections()
+            .dense_grid("map", &mut |ch, _| Ok(ch))
+            .unwrap_err();
+        assert_eq!(err.section, "section 1 (map)");
+        assert_eq!(err.line, 2);
+    }
 }
@@ -1,5 +1,6 @@
 use lazy_regex::regex;
 use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 
 use crate::solver::SolverState;
 
@@ -59,18 +60,25 @@ where
     }
 
     pub fn from_set(coords: &HashSet<Coord>, val: T) -> Self {
-        let mut grid = Grid {
-            coords: coords
-                .into_iter()
-                .map(|c| (*c, val))
-                .collect::<HashMap<Coord, T>>(),
-            min: Coord { x: 0, y: 0 },
-            max: Coord { x: 0, y: 0 },
-        };
-        grid.recompute_minmax();
+        let mut grid = Grid::new();
+        for coord in coords {
+            grid.insert(*coord, val);
+        }
         return grid;
     }
 
+    // Sets the value at coord, growing min and max to take it in if they need to (or
+    // starting them there if the grid was empty)
+    pub fn insert(&mut self, coord: Coord, val: T) {
+        if self.coords.is_empty() {
+            self.min = coord;
+            self.max = coord;
+        } else {
+            extend_bounds(&mut self.min, &mut self.max, &coord);
+        }
+        self.coords.insert(coord, val);
+    }
+
     pub fn recompute_minmax(&mut self) {
         let mut min_x = i32::MAX;
         let mut min_y = i32::MAX;
@@ -95,6 +103,145 @@ where
     }
 }
 
+fn extend_bounds(min: &mut Coord, max: &mut Coord, coord: &Coord) {
+    min.x = min.x.min(coord.x);
+    min.y = min.y.min(coord.y);
+    max.x = max.x.max(coord.x);
+    max.y = max.y.max(coord.y);
+}
+
+// Something that can be an InfiniteGrid's coords: extend_bounds grows min and max just
+// enough to take in coord, and in_bounds says whether it's between them (inclusive)
+pub trait GridCoord: Copy + Eq + Hash {
+    fn extend_bounds(min: &mut Self, max: &mut Self, coord: &Self);
+    fn in_bounds(&self, min: &Self, max: &Self) -> bool;
+}
+
+impl GridCoord for Coord {
+    fn extend_bounds(min: &mut Coord, max: &mut Coord, coord: &Coord) {
+        extend_bounds(min, max, coord);
+    }
+
+    fn in_bounds(&self, min: &Coord, max: &Coord) -> bool {
+        return self.x >= min.x && self.x <= max.x && self.y >= min.y && self.y <= max.y;
+    }
+}
+
+// A grid that goes on forever, where every coord that hasn't been set has the default
+// value (which can be different for each generation of something like 2021 day20's
+// image). Only cells that aren't the default get stored, and the bounds grow as they're
+// set, so they always cover all of those but don't shrink again when one goes back to
+// the default unless shrink_bounds is called.
+//
+// It works with other kinds of coords too (anything that's a GridCoord, like Coord3d or
+// CoordN), but only a 2d one can be turned into a Grid.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct InfiniteGrid<T, C = Coord>
+where
+    C: GridCoord,
+{
+    coords: HashMap<C, T>,
+    default: T,
+    bounds: Option<(C, C)>,
+}
+
+impl<T, C> InfiniteGrid<T, C>
+where
+    T: PartialEq + Clone,
+    C: GridCoord,
+{
+    pub fn new(default: T) -> Self {
+        return InfiniteGrid { coords: HashMap::new(), default, bounds: None };
+    }
+
+    pub fn default(&self) -> &T {
+        return &self.default;
+    }
+
+    // Changes what every cell that hasn't been set is, eg when 2021 day20's background
+    // flips. Cells that were set to the new default stop being stored, but the bounds
+    // stay the same until shrink_bounds is called.
+    pub fn set_default(&mut self, default: T) {
+        self.coords.retain(|_, val| *val != default);
+        self.default = default;
+    }
+
+    pub fn get(&self, coord: &C) -> &T {
+        return self.coords.get(coord).unwrap_or(&self.default);
+    }
+
+    pub fn set(&mut self, coord: C, val: T) {
+        if val == self.default {
+            self.coords.remove(&coord);
+            return;
+        }
+        match &mut self.bounds {
+            Some((min, max)) => C::extend_bounds(min, max, &coord),
+            None => self.bounds = Some((coord, coord)),
+        }
+        self.coords.insert(coord, val);
+    }
+
+    // The cells that aren't the default, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&C, &T)> {
+        return self.coords.iter();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.coords.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.coords.is_empty();
+    }
+
+    // The smallest and largest coords of anything that's been set to something other
+    // than the default, or None if nothing has
+    pub fn bounds(&self) -> Option<(C, C)> {
+        return self.bounds;
+    }
+
+    pub fn in_bounds(&self, coord: &C) -> bool {
+        return match &self.bounds {
+            Some((min, max)) => coord.in_bounds(min, max),
+            None => false,
+        };
+    }
+
+    // Recomputes the bounds so they're tight around the cells that are still set
+    pub fn shrink_bounds(&mut self) {
+        let mut bounds: Option<(C, C)> = None;
+        for coord in self.coords.keys() {
+            match &mut bounds {
+                Some((min, max)) => C::extend_bounds(min, max, coord),
+                None => bounds = Some((*coord, *coord)),
+            }
+        }
+        self.bounds = bounds;
+    }
+}
+
+impl<T> InfiniteGrid<T>
+where
+    T: PartialEq + Clone,
+{
+    pub fn from_grid(grid: &Grid<T>, default: T) -> Self {
+        let mut infinite = InfiniteGrid::new(default);
+        for (coord, val) in &grid.coords {
+            infinite.set(*coord, val.clone());
+        }
+        return infinite;
+    }
+
+    // Just the cells that aren't the default, with the bounds as min and max
+    pub fn to_grid(&self) -> Grid<T> {
+        let (min, max) = self
+            .bounds
+            .unwrap_or((Coord { x: 0, y: 0 }, Coord { x: 0, y: 0 }));
+        return Grid { coords: self.coords.clone(), min, max };
+    }
+}
+
 pub fn four_neighbors(coord: &Coord) -> Vec<Coord> {
     return vec![
         Coord { x: coord.x + 1, y: coord.y },
@@ -240,3 +387,99 @@ pub fn turn_left(dir: &Direction) -> Direction {
 pub fn turn_right(dir: &Direction) -> Direction {
     return Direction { dx: -dir.dy, dy: dir.dx };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infinite_grid() {
+        let mut grid = InfiniteGrid::new('.');
+        assert!(grid.is_empty());
+        assert_eq!(grid.bounds(), None);
+        assert!(!grid.in_bounds(&Coord { x: 0, y: 0 }));
+
+        grid.set(Coord { x: 2, y: -1 }, '#');
+        grid.set(Coord { x: -3, y: 4 }, '#');
+        // setting the default doesn't store anything or grow the bounds
+        grid.set(Coord { x: 10, y: 10 }, '.');
+        assert_eq!(grid.len(), 2);
+        assert_eq!(*grid.get(&Coord { x: 2, y: -1 }), '#');
+        assert_eq!(*grid.get(&Coord { x: 100, y: 100 }), '.');
+        assert_eq!(
+            grid.bounds(),
+            Some((Coord { x: -3, y: -1 }, Coord { x: 2, y: 4 }))
+        );
+        assert!(grid.in_bounds(&Coord { x: 0, y: 0 }));
+        assert!(!grid.in_bounds(&Coord { x: 10, y: 10 }));
+    }
+
+    #[test]
+    fn test_shrink_bounds() {
+        let mut grid = InfiniteGrid::new(0);
+        grid.set(Coord { x: 0, y: 0 }, 1);
+        grid.set(Coord { x: 5, y: 5 }, 2);
+        let before = grid.bounds();
+
+        // the bounds stay put when a cell goes back to the default, until they're shrunk
+        grid.set(Coord { x: 5, y: 5 }, 0);
+        assert_eq!(grid.bounds(), before);
+        grid.shrink_bounds();
+        assert_eq!(
+            grid.bounds(),
+            Some((Coord { x: 0, y: 0 }, Coord { x: 0, y: 0 }))
+        );
+
+        grid.set(Coord { x: 5, y: 5 }, 2);
+        assert_eq!(grid.bounds(), before);
+        grid.shrink_bounds();
+        assert_eq!(grid.bounds(), before);
+
+        grid.set(Coord { x: 0, y: 0 }, 0);
+        grid.set(Coord { x: 5, y: 5 }, 0);
+        grid.shrink_bounds();
+        assert!(grid.is_empty());
+        assert_eq!(grid.bounds(), None);
+    }
+
+    #[test]
+    fn test_set_default() {
+        let mut grid = InfiniteGrid::new(false);
+        grid.set(Coord { x: 1, y: 1 }, true);
+        grid.set(Coord { x: 3, y: 2 }, true);
+
+        // the lit cells are now the same as the background, so they aren't stored
+        grid.set_default(true);
+        assert!(grid.is_empty());
+        assert!(*grid.get(&Coord { x: -50, y: 7 }));
+        assert!(grid.bounds().is_some());
+
+        grid.set(Coord { x: 0, y: 0 }, false);
+        assert_eq!(grid.len(), 1);
+        grid.set_default(false);
+        assert!(grid.is_empty());
+        grid.shrink_bounds();
+        assert_eq!(grid.bounds(), None);
+    }
+
+    #[test]
+    fn test_infinite_grid_round_trip() {
+        let mut grid = Grid::new();
+        grid.insert(Coord { x: 1, y: 2 }, 'a');
+        grid.insert(Coord { x: 3, y: 0 }, 'b');
+        grid.insert(Coord { x: 2, y: 2 }, '.');
+
+        // the default cell gets dropped
+        let infinite = InfiniteGrid::from_grid(&grid, '.');
+        assert_eq!(infinite.len(), 2);
+        assert_eq!(*infinite.get(&Coord { x: 2, y: 2 }), '.');
+
+        let back = infinite.to_grid();
+        assert_eq!(back.coords.len(), 2);
+        assert_eq!(back.coords.get(&Coord { x: 3, y: 0 }), Some(&'b'));
+        assert_eq!(
+            (back.min, back.max),
+            (Coord { x: 1, y: 0 }, Coord { x: 3, y: 2 })
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use crate::parse_error::ParseError;
+
+// Cube coordinates on a grid of pointy-topped hexes, so each one has neighbors to the e
+// and w and then ne, nw, se and sw. q + r + s is always 0, so the axial coordinates
+// (q, r) are enough to say where it is, and s is just there to make the math simpler.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug, Hash)]
+pub struct Hex {
+    pub q: i32,
+    pub r: i32,
+    pub s: i32,
+}
+
+impl Hex {
+    pub fn axial(q: i32, r: i32) -> Hex {
+        return Hex { q, r, s: -q - r };
+    }
+
+    pub fn cube(q: i32, r: i32, s: i32) -> Hex {
+        if q + r + s != 0 {
+            panic!("Bad cube coords (don't add up to 0): {}, {}, {}", q, r, s);
+        }
+        return Hex { q, r, s };
+    }
+
+    pub fn origin() -> Hex {
+        return Hex { q: 0, r: 0, s: 0 };
+    }
+
+    pub fn to_axial(&self) -> (i32, i32) {
+        return (self.q, self.r);
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug, Hash)]
+pub enum HexDirection {
+    E,
+    NE,
+    NW,
+    W,
+    SW,
+    SE,
+}
+
+// Going counterclockwise from e
+pub const HEX_DIRECTIONS: [HexDirection; 6] = [
+    HexDirection::E,
+    HexDirection::NE,
+    HexDirection::NW,
+    HexDirection::W,
+    HexDirection::SW,
+    HexDirection::SE,
+];
+
+impl HexDirection {
+    // "e", "ne", "nw", "w", "sw" or "se"
+    pub fn parse(val: &str) -> Option<HexDirection> {
+        return match val {
+            "e" => Some(HexDirection::E),
+            "ne" => Some(HexDirection::NE),
+            "nw" => Some(HexDirection::NW),
+            "w" => Some(HexDirection::W),
+            "sw" => Some(HexDirection::SW),
+            "se" => Some(HexDirection::SE),
+            _ => None,
+        };
+    }
+
+    pub fn offset(&self) -> Hex {
+        return match self {
+            HexDirection::E => Hex { q: 1, r: 0, s: -1 },
+            HexDirection::NE => Hex { q: 1, r: -1, s: 0 },
+            HexDirection::NW => Hex { q: 0, r: -1, s: 1 },
+            HexDirection::W => Hex { q: -1, r: 0, s: 1 },
+            HexDirection::SW => Hex { q: -1, r: 1, s: 0 },
+            HexDirection::SE => Hex { q: 0, r: 1, s: -1 },
+        };
+    }
+}
+
+// A run of directions with nothing between them, like "esenee". The error has the column
+// (in chars, like the rest of the parsing) of the first thing that isn't a direction.
+pub fn parse_hex_path(line: &str) -> Result<Vec<HexDirection>, ParseError> {
+    let chars = line.chars().collect::<Vec<char>>();
+    let mut path = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let len = if chars[idx] == 'n' || chars[idx] == 's' {
+            2
+        } else {
+            1
+        };
+        let text = chars[idx..chars.len().min(idx + len)]
+            .iter()
+            .collect::<String>();
+        match HexDirection::parse(&text) {
+            Some(dir) => path.push(dir),
+            None => {
+                return Err(ParseError::new("Bad hex direction")
+                    .at_column(idx + 1)
+                    .at_line(0, line));
+            }
+        }
+        idx += len;
+    }
+    return Ok(path);
+}
+
+pub fn add_hex_direction(start: &Hex, dir: &HexDirection) -> Hex {
+    let offset = dir.offset();
+    return Hex {
+        q: start.q + offset.q,
+        r: start.r + offset.r,
+        s: start.s + offset.s,
+    };
+}
+
+// In the same order as HEX_DIRECTIONS
+pub fn hex_neighbors(hex: &Hex) -> Vec<Hex> {
+    return HEX_DIRECTIONS
+        .iter()
+        .map(|dir| add_hex_direction(hex, dir))
+        .collect();
+}
+
+// How many steps it takes to get from one to the other
+pub fn hex_distance(start: &Hex, end: &Hex) -> i32 {
+    return ((end.q - start.q).abs() + (end.r - start.r).abs() + (end.s - start.s).abs()) / 2;
+}
+
+// Every hex exactly radius steps from center, going counterclockwise from the one that's
+// furthest sw. A ring of radius 0 is just the center.
+pub fn hex_ring(center: &Hex, radius: i32) -> Vec<Hex> {
+    if radius == 0 {
+        return vec![*center];
+    }
+    let mut cur = *center;
+    for _ in 0..radius {
+        cur = add_hex_direction(&cur, &HexDirection::SW);
+    }
+    let mut ring = Vec::new();
+    for dir in &HEX_DIRECTIONS {
+        for _ in 0..radius {
+            ring.push(cur);
+            cur = add_hex_direction(&cur, dir);
+        }
+    }
+    return ring;
+}
+
+// Every hex up to radius steps from center, ring by ring going out from the center
+pub fn hex_spiral(center: &Hex, radius: i32) -> Vec<Hex> {
+    return (0..=radius)
+        .flat_map(|ring| hex_ring(center, ring))
+        .collect();
+}
+
+// Rotates hex around center by 60 degrees for each turn, clockwise if turns is positive
+// and counterclockwise if it's negative
+pub fn rotate_hex(hex: &Hex, center: &Hex, turns: i32) -> Hex {
+    let (mut q, mut r, mut s) = (hex.q - center.q, hex.r - center.r, hex.s - center.s);
+    for _ in 0..turns.rem_euclid(6) {
+        (q, r, s) = (-r, -s, -q);
+    }
+    return Hex { q: center.q + q, r: center.r + r, s: center.s + s };
+}
+
+// Draws every hex in the box around the given ones, with each row shifted over half a
+// hex from the one above it, eg for a spiral of radius 1:
+//
+//      # #
+//     # # #
+//      # #
+pub fn render_hexes<'a, I, F>(hexes: I, render_one: &mut F) -> String
+where
+    I: IntoIterator<Item = &'a Hex>,
+    F: FnMut(&Hex) -> char,
+{
+    // in "doubled" coords, where a hex's column is 2q + r so neighbors in the same row are
+    // two apart and the ones above and below are one over
+    let mut bounds: Option<(i32, i32, i32, i32)> = None;
+    for hex in hexes {
+        let col = 2 * hex.q + hex.r;
+        bounds = Some(match bounds {
+            Some((min_col, max_col, min_r, max_r)) => (
+                min_col.min(col),
+                max_col.max(col),
+                min_r.min(hex.r),
+                max_r.max(hex.r),
+            ),
+            None => (col, col, hex.r, hex.r),
+        });
+    }
+    let (min_col, max_col, min_r, max_r) = match bounds {
+        Some(bounds) => bounds,
+        None => return String::new(),
+    };
+
+    let mut lines = Vec::new();
+    for r in min_r..=max_r {
+        let mut line = String::new();
+        for col in min_col..=max_col {
+            if (col - r).rem_euclid(2) == 0 {
+                line.push(render_one(&Hex::axial((col - r) / 2, r)));
+            } else {
+                line.push(' ');
+            }
+        }
+        lines.push(line.trim_end().to_string());
+    }
+    return lines.join("\n");
+}
+
+pub fn print_hexes<'a, I, F>(hexes: I, render_one: &mut F) -> ()
+where
+    I: IntoIterator<Item = &'a Hex>,
+    F: FnMut(&Hex) -> char,
+{
+    println!("{}", render_hexes(hexes, render_one));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_parse_hex_path() {
+        use HexDirection::*;
+        assert_eq!(parse_hex_path("esenee").unwrap(), vec![E, SE, NE, E]);
+        assert_eq!(parse_hex_path("").unwrap(), vec![]);
+        assert_eq!(parse_hex_path("eswx").unwrap_err().column, 4);
+        // a lone n or s at the end isn't a direction
+        assert_eq!(parse_hex_path("en").unwrap_err().column, 2);
+        assert_eq!(parse_hex_path("wé").unwrap_err().column, 2);
+        assert_eq!(parse_hex_path("sé").unwrap_err().column, 1);
+    }
+
+    #[test]
+    fn test_hex_ring() {
+        let center = Hex::axial(2, -1);
+        assert_eq!(hex_ring(&center, 0), vec![center]);
+        for radius in 1..=3 {
+            let ring = hex_ring(&center, radius);
+            assert_eq!(ring.len(), 6 * radius as usize);
+            assert_eq!(ring.iter().collect::<HashSet<_>>().len(), ring.len());
+            assert!(ring.iter().all(|hex| hex_distance(&center, hex) == radius));
+        }
+        assert_eq!(hex_spiral(&center, 2).len(), 19);
+
+        let mut neighbors = hex_ring(&Hex::origin(), 1);
+        neighbors.sort();
+        let mut expected = hex_neighbors(&Hex::origin());
+        expected.sort();
+        assert_eq!(neighbors, expected);
+    }
+
+    #[test]
+    fn test_rotate_hex() {
+        let east = HexDirection::E.offset();
+        let origin = Hex::origin();
+        assert_eq!(rotate_hex(&east, &origin, 1), HexDirection::SE.offset());
+        assert_eq!(rotate_hex(&east, &origin, -1), HexDirection::NE.offset());
+        assert_eq!(rotate_hex(&east, &origin, 3), HexDirection::W.offset());
+        assert_eq!(rotate_hex(&east, &origin, 6), east);
+
+        let center = Hex::axial(3, 4);
+        let hex = Hex::axial(5, 3);
+        let turned = rotate_hex(&hex, &center, 2);
+        assert_eq!(hex_distance(&center, &turned), hex_distance(&center, &hex));
+        assert_eq!(rotate_hex(&turned, &center, -2), hex);
+    }
+
+    #[test]
+    fn test_render_hexes() {
+        let spiral = hex_spiral(&Hex::origin(), 1);
+        assert_eq!(render_hexes(&spiral, &mut |_| '#'), " # #\n# # #\n # #");
+
+        let marked = vec![Hex::origin(), Hex::axial(2, 0)];
+        let drawn = render_hexes(&marked, &mut |hex| {
+            if marked.contains(hex) {
+                '#'
+            } else {
+                '.'
+            }
+        });
+        assert_eq!(drawn, "# . #");
+        assert_eq!(render_hexes(&Vec::new(), &mut |_| '#'), "");
+    }
+}
@@ -1,5 +1,15 @@
+pub mod automaton;
+pub mod bench;
+pub mod coordn;
+pub mod dense_grid;
 pub mod framework;
 pub mod grid;
 pub mod grid3d;
+pub mod hex;
+pub mod parse_error;
+pub mod runner;
+pub mod scan;
 pub mod solver;
+pub mod testing;
 pub mod utils;
+pub mod verify;
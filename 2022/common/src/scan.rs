@@ -0,0 +1,254 @@
+use lazy_regex::{regex, Captures, Regex};
+
+use crate::parse_error::ParseError;
+
+// Makes a struct along with a FromStr for it that reads lines in the given format, where
+// {name} is where a field goes and [...] is a part that might not be there:
+//
+//     common::line_format! {
+//         #[parse("Valve {name} has flow rate={flow}; tunnel[s] lead[s] to valve[s] {next}")]
+//         struct Valve {
+//             name: String,
+//             flow: i32,
+//             next: Vec<String>,
+//         }
+//     }
+//
+// Fields can be anything ScanField is implemented for: numbers, chars, bools and strings,
+// Option of one of those (which is None if the field was in a [...] that wasn't there),
+// and Vec of one of those (which is split on commas). The FromStr gives a ParseError, so
+// framework::parse_lines_as can read a whole section of these at once.
+#[macro_export]
+macro_rules! line_format {
+    (
+        #[parse($format:literal)]
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($field_vis:vis $field:ident: $type:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($field_vis $field: $type),*
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = $crate::parse_error::ParseError;
+
+            fn from_str(line: &str) -> Result<Self, Self::Err> {
+                static FORMAT: std::sync::OnceLock<$crate::scan::LineFormat> =
+                    std::sync::OnceLock::new();
+                let format = FORMAT.get_or_init(|| {
+                    $crate::scan::LineFormat::new($format, &[$(stringify!($field)),*])
+                });
+                let scanned = format.scan(line)?;
+                return Ok($name {
+                    $($field: scanned.get(stringify!($field))?),*
+                });
+            }
+        }
+    };
+}
+
+pub trait ScanField: Sized {
+    // text is None for a field in an optional part that wasn't there
+    fn scan_field(text: Option<&str>) -> Result<Self, String>;
+}
+
+macro_rules! scan_field_from_str {
+    ($($type:ty),*) => {
+        $(
+            impl ScanField for $type {
+                fn scan_field(text: Option<&str>) -> Result<Self, String> {
+                    return match text {
+                        Some(text) => text
+                            .trim()
+                            .parse::<$type>()
+                            .map_err(|err| format!("Can't parse {:?}: {}", text, err)),
+                        None => Err("Missing value".to_string()),
+                    };
+                }
+            }
+        )*
+    };
+}
+
+scan_field_from_str!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, char, bool, String
+);
+
+impl<T: ScanField> ScanField for Option<T> {
+    fn scan_field(text: Option<&str>) -> Result<Self, String> {
+        return match text {
+            Some(text) => T::scan_field(Some(text)).map(Some),
+            None => Ok(None),
+        };
+    }
+}
+
+impl<T: ScanField> ScanField for Vec<T> {
+    fn scan_field(text: Option<&str>) -> Result<Self, String> {
+        let sep = regex!(r#"\s*,\s*"#);
+        return match text {
+            Some(text) => sep
+                .split(text.trim())
+                .map(|val| T::scan_field(Some(val)))
+                .collect(),
+            None => Ok(Vec::new()),
+        };
+    }
+}
+
+fn push_literal(pattern: &mut String, ch: char) {
+    if "\\.+*?()|[]{}^$#&-~".contains(ch) {
+        pattern.push('\\');
+    }
+    pattern.push(ch);
+}
+
+// A format like "Sensor at x={x}, y={y}", turned into a regex with a group for each
+// field. {{ and }} are literal braces, and \ makes the next character literal, eg \[.
+// Every one of fields has to have a {...} in the format and the other way round, so a
+// typo in either one is caught when the format's made rather than when a line doesn't
+// have a value for it.
+pub struct LineFormat {
+    format: String,
+    rex: Regex,
+}
+
+impl LineFormat {
+    pub fn new(format: &str, fields: &[&str]) -> LineFormat {
+        let mut pattern = String::from("^");
+        let mut chars = format.chars().peekable();
+        let mut depth = 0;
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' | '}' if chars.peek() == Some(&ch) => {
+                    chars.next();
+                    push_literal(&mut pattern, ch);
+                }
+                '\\' => match chars.next() {
+                    Some(next) => push_literal(&mut pattern, next),
+                    None => panic!("Format ends with a \\: {}", format),
+                },
+                '{' => {
+                    let name = chars.by_ref().take_while(|c| *c != '}').collect::<String>();
+                    pattern.push_str(&format!("(?P<{}>.+?)", name.trim()));
+                }
+                '[' => {
+                    depth += 1;
+                    pattern.push_str("(?:");
+                }
+                ']' => {
+                    if depth == 0 {
+                        panic!("Unmatched ] in format: {}", format);
+                    }
+                    depth -= 1;
+                    pattern.push_str(")?");
+                }
+                _ => push_literal(&mut pattern, ch),
+            }
+        }
+        if depth != 0 {
+            panic!("Unmatched [ in format: {}", format);
+        }
+        pattern.push('$');
+
+        let rex = match Regex::new(&pattern) {
+            Ok(rex) => rex,
+            Err(err) => panic!("Bad format {}: {}", format, err),
+        };
+        let groups = rex.capture_names().flatten().collect::<Vec<&str>>();
+        for field in fields {
+            if !groups.contains(field) {
+                panic!("No {{{}}} in format for field {}: {}", field, field, format);
+            }
+        }
+        for group in &groups {
+            if !fields.contains(group) {
+                panic!("No field for {{{}}} in format: {}", group, format);
+            }
+        }
+        return LineFormat { format: format.to_string(), rex };
+    }
+
+    pub fn scan<'a>(&self, line: &'a str) -> Result<Scanned<'a>, ParseError> {
+        return match self.rex.captures(line) {
+            Some(caps) => Ok(Scanned { line, caps }),
+            None => {
+                Err(ParseError::new(&format!("Doesn't match {:?}", self.format)).at_line(0, line))
+            }
+        };
+    }
+}
+
+pub struct Scanned<'a> {
+    line: &'a str,
+    caps: Captures<'a>,
+}
+
+impl<'a> Scanned<'a> {
+    // The error has the column the field starts at, counted in chars like everywhere else
+    // rather than the bytes the match gives
+    pub fn get<T: ScanField>(&self, name: &str) -> Result<T, ParseError> {
+        let found = self.caps.name(name);
+        return T::scan_field(found.map(|m| m.as_str())).map_err(|msg| {
+            let column = found.map_or(0, |m| self.line[..m.start()].chars().count() + 1);
+            ParseError::new(&format!("{}: {}", name, msg))
+                .at_column(column)
+                .at_line(0, self.line)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::line_format! {
+        #[parse("{name} at {x}°, {y}°[ ({note})]")]
+        #[derive(PartialEq, Debug)]
+        struct Point {
+            name: String,
+            x: i32,
+            y: i32,
+            note: Option<String>,
+        }
+    }
+
+    #[test]
+    fn test_scan() {
+        let point = "Café at 1°, -2°".parse::<Point>().unwrap();
+        assert_eq!(
+            point,
+            Point { name: "Café".to_string(), x: 1, y: -2, note: None }
+        );
+        let point = "Bar at 3°, 4° (closed)".parse::<Point>().unwrap();
+        assert_eq!(point.note, Some("closed".to_string()));
+    }
+
+    #[test]
+    fn test_format_matches_fields() {
+        let format = LineFormat::new("{a} and [{b}]", &["b", "a"]);
+        assert!(format.scan("1 and 2").is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "No {y} in format for field y: x={x}, y={why}")]
+    fn test_field_without_group() {
+        LineFormat::new("x={x}, y={why}", &["x", "y"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "No field for {z} in format: {x},{y},{z}")]
+    fn test_group_without_field() {
+        LineFormat::new("{x},{y},{z}", &["x", "y"]);
+    }
+
+    #[test]
+    fn test_error_column_is_in_chars() {
+        let err = "Café at 1°, x°".parse::<Point>().unwrap_err();
+        assert_eq!(err.column, 13);
+        assert_eq!("Café at 1°, x°".chars().nth(err.column - 1), Some('x'));
+    }
+}
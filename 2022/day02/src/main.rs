@@ -1,9 +1,9 @@
 extern crate common;
-#[macro_use]
-extern crate lazy_static;
 
 use std::collections::HashMap;
 
+use lazy_static::lazy_static;
+
 use common::framework::{parse_lines, run_day, BaseDay, InputReader};
 
 struct Day02 {
@@ -65,7 +65,12 @@ impl BaseDay for Day02 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day02 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day02 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
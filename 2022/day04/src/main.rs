@@ -55,7 +55,12 @@ impl BaseDay for Day04 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day04 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day04 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
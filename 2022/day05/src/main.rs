@@ -1,8 +1,9 @@
-use lazy_regex::regex;
+use lazy_regex::{regex, Captures};
 
 extern crate common;
 
-use common::framework::{parse_lines, run_day, BaseDay, InputReader};
+use common::framework::{run_day, try_parse_regexp, BaseDay, InputReader};
+use common::parse_error::ParseError;
 
 struct Move {
     from: i32,
@@ -43,7 +44,12 @@ fn run_moves(stacks_orig: &Vec<Vec<char>>, moves: &Vec<Move>, single: bool) -> V
 
 impl BaseDay for Day05 {
     fn parse(&mut self, input: &mut InputReader) {
-        fn parse_stackline(line: String, stacks: &mut Vec<Vec<char>>) -> bool {
+        self.try_parse(input)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    fn try_parse(&mut self, input: &mut InputReader) -> Result<(), ParseError> {
+        fn parse_stackline(line: String, stacks: &mut Vec<Vec<char>>) {
             for (idx, ch) in line.chars().skip(1).step_by(4).enumerate() {
                 if stacks.len() <= idx {
                     stacks.push(Vec::new());
@@ -52,27 +58,24 @@ impl BaseDay for Day05 {
                     stacks[idx].insert(0, ch);
                 }
             }
-            return true;
         }
 
-        fn parse_move(line: String) -> Move {
+        fn parse_move(line: String) -> Result<Move, ParseError> {
             let rex = regex!(r#"move\s+(\d+)\s+from\s+(\d+)\s+to\s+(\d+)\s*"#);
-            match rex.captures(&line) {
-                Some(c) => {
-                    return Move {
-                        from: c[2].parse::<i32>().unwrap() - 1,
-                        to: c[3].parse::<i32>().unwrap() - 1,
-                        amount: c[1].parse::<i32>().unwrap(),
-                    }
-                }
-                None => panic!("Bad line: {}", &line),
-            };
+            return try_parse_regexp(&line, rex, &mut |c: Captures| Move {
+                from: c[2].parse::<i32>().unwrap() - 1,
+                to: c[3].parse::<i32>().unwrap() - 1,
+                amount: c[1].parse::<i32>().unwrap(),
+            });
         }
 
-        parse_lines(input, &mut |line: String| {
-            parse_stackline(line, &mut self.stacks)
-        });
-        self.moves = parse_lines(input, &mut parse_move);
+        let mut sections = input.sections();
+        sections.lines("stacks", &mut |line: String| {
+            parse_stackline(line, &mut self.stacks);
+            Ok(())
+        })?;
+        self.moves = sections.lines("moves", &mut parse_move)?;
+        return Ok(());
     }
 
     fn pt1(&mut self) -> String {
@@ -92,7 +95,12 @@ impl BaseDay for Day05 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day05 { stacks: Vec::new(), moves: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day05 { stacks: Vec::new(), moves: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
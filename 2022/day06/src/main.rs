@@ -58,7 +58,12 @@ impl BaseDay for Day06 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day06 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day06 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
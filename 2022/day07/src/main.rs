@@ -176,7 +176,12 @@ impl BaseDay for Day07 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day07 { nodes: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day07 { nodes: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
@@ -2,34 +2,34 @@ use std::collections::{HashMap, HashSet};
 
 extern crate common;
 
-use common::framework::{parse_grid, run_day, BaseDay, InputReader};
-use common::grid::{add_direction, print_grid, Coord, Direction, Grid};
+use common::dense_grid::DenseGrid;
+use common::framework::{parse_dense_grid, run_day, BaseDay, InputReader};
+use common::grid::{add_direction, Coord, Direction};
 
 struct Day08 {
-    vals: Grid<i32>,
+    vals: DenseGrid<i32>,
 }
 
 impl BaseDay for Day08 {
     fn parse(&mut self, input: &mut InputReader) {
-        fn parse_coord(c: char, _coord: &Coord) -> Option<i32> {
-            let val = c.to_digit(10).unwrap() as i32;
-            Some(val)
+        fn parse_coord(c: char, _coord: &Coord) -> i32 {
+            return c.to_digit(10).unwrap() as i32;
         }
 
-        self.vals = parse_grid(input, &mut parse_coord);
+        self.vals = parse_dense_grid(input, &mut parse_coord);
     }
 
     fn pt1(&mut self) -> String {
         fn add_visible(
             start: Coord,
             dir: Direction,
-            grid: &Grid<i32>,
+            grid: &DenseGrid<i32>,
             visible: &mut HashSet<Coord>,
         ) {
             let mut cur = start;
             let mut cur_max = -1;
             loop {
-                if let Some(v) = grid.coords.get(&cur) {
+                if let Some(v) = grid.get(&cur) {
                     if *v > cur_max {
                         visible.insert(cur);
                         cur_max = *v;
@@ -76,12 +76,12 @@ impl BaseDay for Day08 {
     }
 
     fn pt2(&mut self) -> String {
-        fn count_visible(start: Coord, dir: Direction, grid: &Grid<i32>) -> i32 {
-            let limit = *grid.coords.get(&start).unwrap();
+        fn count_visible(start: Coord, dir: Direction, grid: &DenseGrid<i32>) -> i32 {
+            let limit = grid[start];
             let mut cur = add_direction(&start, &dir);
             let mut cnt = 0;
             loop {
-                if let Some(v) = grid.coords.get(&cur) {
+                if let Some(v) = grid.get(&cur) {
                     if *v < limit {
                         cnt += 1;
                         cur = add_direction(&cur, &dir);
@@ -96,7 +96,7 @@ impl BaseDay for Day08 {
         }
 
         let mut scores: HashMap<Coord, i32> = HashMap::new();
-        for &coord in self.vals.coords.keys() {
+        for coord in self.vals.coords() {
             let score = count_visible(coord, Direction { dx: 0, dy: 1 }, &self.vals)
                 * count_visible(coord, Direction { dx: 1, dy: 0 }, &self.vals)
                 * count_visible(coord, Direction { dx: 0, dy: -1 }, &self.vals)
@@ -111,7 +111,12 @@ impl BaseDay for Day08 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day08 { vals: DenseGrid::from_rows(Vec::new()) });
+}
+
 fn main() {
-    let mut day = Day08 { vals: Grid::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
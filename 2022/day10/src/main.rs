@@ -3,7 +3,8 @@ use lazy_regex::regex;
 
 extern crate common;
 
-use common::framework::{parse_lines, run_day, BaseDay, InputReader};
+use common::framework::{run_day, try_parse_lines, BaseDay, InputReader};
+use common::parse_error::ParseError;
 
 enum Op {
     Noop(),
@@ -86,19 +87,25 @@ fn draw(ops: &Vec<Op>) -> Vec<String> {
 
 impl BaseDay for Day10 {
     fn parse(&mut self, input: &mut InputReader) {
-        self.vals = parse_lines(input, &mut |line: String| {
+        self.try_parse(input)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    fn try_parse(&mut self, input: &mut InputReader) -> Result<(), ParseError> {
+        self.vals = try_parse_lines(input, &mut |line: String| {
             if line == "noop" {
-                return Op::Noop();
+                return Ok(Op::Noop());
             }
             match regex!(r#"addx\s+(-?\d+)"#).captures(&line) {
                 Some(c) => {
                     let amt = c[1].parse::<i32>().unwrap();
-                    return Op::Addx(amt);
+                    return Ok(Op::Addx(amt));
                 }
                 None => {}
             }
-            panic!("Bad line: {}", line);
-        });
+            return Err(ParseError::new("Bad op"));
+        })?;
+        return Ok(());
     }
 
     fn pt1(&mut self) -> String {
@@ -119,7 +126,12 @@ impl BaseDay for Day10 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day10 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day10 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
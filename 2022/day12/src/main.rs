@@ -2,7 +2,7 @@ extern crate common;
 
 use common::framework::{parse_grid, run_day, BaseDay, InputReader};
 use common::grid::{four_neighbors, Coord, Grid};
-use common::solver::{cost_minimizing_bfs, SolverBase};
+use common::solver::{cost_minimizing_astar, SolverBase};
 
 struct Day12 {
     vals: Grid<i32>,
@@ -33,6 +33,11 @@ impl SolverBase<Coord> for Day12 {
         return false;
     }
 
+    // can't get anywhere faster than one step per square
+    fn heuristic(&self, state: &Coord) -> i32 {
+        return (self.end.x - state.x).abs() + (self.end.y - state.y).abs();
+    }
+
     fn cant_solve(&self) -> i32 {
         return self.vals.max.x * self.vals.max.y + 1;
     }
@@ -55,7 +60,7 @@ impl BaseDay for Day12 {
     }
 
     fn pt1(&mut self) -> String {
-        return cost_minimizing_bfs(self, &self.start).to_string();
+        return cost_minimizing_astar(self, &self.start).cost.to_string();
     }
 
     fn pt2(&mut self) -> String {
@@ -67,18 +72,23 @@ impl BaseDay for Day12 {
             .collect::<Vec<Coord>>();
         return starts
             .into_iter()
-            .map(|s| cost_minimizing_bfs(self, &s))
+            .map(|s| cost_minimizing_astar(self, &s).cost)
             .min()
             .unwrap()
             .to_string();
     }
 }
 
-fn main() {
-    let mut day = Day12 {
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day12 {
         vals: Grid::new(),
         start: Coord { x: 0, y: 0 },
         end: Coord { x: 0, y: 0 },
-    };
-    run_day(&mut day);
+    });
 }
+
+fn main() {
+    run_day(make_day);
+}
+
+common::expected_tests!(make_day, small, input);
@@ -86,12 +86,10 @@ impl BaseDay for Day14 {
         }
 
         parse_lines(input, &mut |line: String| {
-            self.vals
-                .coords
-                .extend(parse_line(&line).into_iter().map(|c| (c, '#')))
+            for coord in parse_line(&line) {
+                self.vals.insert(coord, '#');
+            }
         });
-
-        self.vals.recompute_minmax();
     }
 
     fn pt1(&mut self) -> String {
@@ -105,7 +103,12 @@ impl BaseDay for Day14 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day14 { vals: Grid::new() });
+}
+
 fn main() {
-    let mut day = Day14 { vals: Grid::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
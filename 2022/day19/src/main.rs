@@ -246,7 +246,17 @@ impl BaseDay for Day19 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day19 { vals: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day19 { vals: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(
+    make_day,
+    small,
+    #[ignore = "slow without --release"]
+    input
+);
@@ -444,7 +444,12 @@ impl BaseDay for Day22 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day22 { grid: Grid::new(), steps: Vec::new() });
+}
+
 fn main() {
-    let mut day = Day22 { grid: Grid::new(), steps: Vec::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);
@@ -126,7 +126,12 @@ impl BaseDay for Day23 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day23 { grid: Grid::new() });
+}
+
 fn main() {
-    let mut day = Day23 { grid: Grid::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, small2, input);
@@ -4,7 +4,7 @@ extern crate common;
 
 use common::framework::{parse_grid, run_day, BaseDay, InputReader};
 use common::grid::{four_neighbors, print_grid, Coord, Grid};
-use common::solver::{cost_minimizing_bfs, SolverBase, SolverState};
+use common::solver::{cost_minimizing_astar, SolverBase, SolverState};
 use common::utils::mod_one_through_range;
 
 struct Day24 {
@@ -211,14 +211,25 @@ impl SolverBase<State> for Map {
     fn is_verbose(&self) -> bool {
         return false;
     }
+
+    // waiting around for blizzards to pass only makes it longer
+    fn heuristic(&self, state: &State) -> i32 {
+        return (self.end.x - state.pos.x).abs() + (self.end.y - state.pos.y).abs();
+    }
 }
 
 fn walk_grid(grid: &Grid<char>, trips: i32) -> i32 {
     let mut map = make_map(grid);
     let mut total_cost: i32 = 0;
     for _ in 0..trips {
-        let cur_cost = cost_minimizing_bfs(&map, &State { pos: map.start, round: total_cost });
-        total_cost += cur_cost;
+        let solution = cost_minimizing_astar(&map, &State { pos: map.start, round: total_cost });
+        if !solution.is_valid(&map) {
+            panic!(
+                "Solver came back with a route that doesn't work: {:?}",
+                solution.states
+            );
+        }
+        total_cost += solution.cost;
         let tmp = map.start;
         map.start = map.end;
         map.end = tmp;
@@ -247,7 +258,12 @@ impl BaseDay for Day24 {
     }
 }
 
+pub fn make_day() -> Box<dyn BaseDay> {
+    return Box::new(Day24 { vals: Grid::new() });
+}
+
 fn main() {
-    let mut day = Day24 { vals: Grid::new() };
-    run_day(&mut day);
+    run_day(make_day);
 }
+
+common::expected_tests!(make_day, small, input);